@@ -3,7 +3,10 @@ use std::time::Duration;
 use criterion::{criterion_group, criterion_main, Criterion};
 
 mod utils;
-use utils::check_tree_force_simulation_on_random_values;
+use utils::{
+    check_tree_force_simulation_on_random_values,
+    check_tree_force_simulation_on_random_values_with_one_call,
+};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     let mut len = 1000;
@@ -12,6 +15,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     g.bench_function("Barnes-Hut-Tree", |b| {
         b.iter(|| check_tree_force_simulation_on_random_values(len))
     });
+    g.bench_function("Barnes-Hut-Tree (All Values in One Call)", |b| {
+        b.iter(|| check_tree_force_simulation_on_random_values_with_one_call(len))
+    });
     // g.bench_function("Vanillia Double Nested Loops", |b| {
     //     b.iter(|| check_vanillia_force_simulation_on_random_values(len))
     // });
@@ -22,6 +28,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     g.bench_function("Barnes-Hut-Tree", |b| {
         b.iter(|| check_tree_force_simulation_on_random_values(len))
     });
+    g.bench_function("Barnes-Hut-Tree (All Values in One Call)", |b| {
+        b.iter(|| check_tree_force_simulation_on_random_values_with_one_call(len))
+    });
     // g.bench_function("Vanillia Double Nested Loops", |b| {
     //     b.iter(|| check_vanillia_force_simulation_on_random_values(len))
     // });
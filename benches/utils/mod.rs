@@ -39,7 +39,7 @@ pub fn _check_vanillia_force_simulation_on_random_values(
             calc_fn(&values[value_i], &values[value_j], 1, &mut displacement);
         }
 
-        let mut new_value = values[value_i].clone();
+        let mut new_value = values[value_i];
         for d in 0..D {
             new_value[d] += displacement[d];
         }
@@ -63,7 +63,31 @@ pub fn check_tree_force_simulation_on_random_values(
 
         bht.calc_force_on_value(value_i, &is_super_fn, &calc_fn, &mut displacement);
 
-        let mut new_value = bht.get(value_i).expect("Should have").clone();
+        let mut new_value = *bht.get(value_i).expect("Should have");
+        for d in 0..D {
+            new_value[d] += displacement[d];
+        }
+
+        bht.update(value_i, &new_value);
+    }
+    Ok(())
+}
+
+pub fn check_tree_force_simulation_on_random_values_with_one_call(
+    len: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let mut bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0, 0.0], 5.0, &values);
+
+    let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<D>(1.2);
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    let mut displacements = vec![[0.0; D]; len];
+
+    bht.calc_force_on_all_values(&is_super_fn, &calc_fn, &mut displacements);
+
+    for (value_i, displacement) in displacements.iter().enumerate() {
+        let mut new_value = *bht.get(value_i).expect("Should have");
         for d in 0..D {
             new_value[d] += displacement[d];
         }
@@ -1,16 +1,16 @@
 use crate::{colvec::ColVec, Fnum, Udim};
 
 #[derive(Clone, PartialEq, Debug)]
-pub struct BoundBox<const D: Udim> {
-    pub(crate) bc: ColVec<D>,
-    pub(crate) br: Fnum,
+pub struct BoundBox<const D: Udim, F: Fnum> {
+    pub(crate) bc: ColVec<D, F>,
+    pub(crate) br: F,
 }
 
-impl<const D: Udim> BoundBox<D> {
+impl<const D: Udim, F: Fnum> BoundBox<D, F> {
     const DIM: usize = D;
 
     #[inline]
-    pub fn new_with_arr(bc: &[Fnum; D], br: Fnum) -> Self {
+    pub fn new_with_arr(bc: &[F; D], br: F) -> Self {
         assert!(
             br.is_finite(),
             "The range of a bounding box does not seem finite..."
@@ -21,8 +21,75 @@ impl<const D: Udim> BoundBox<D> {
         }
     }
 
+    /// Calculate the minimal hypercube containing all the values.
+    ///
+    /// Since the upper bounds are exclusive, the radius is slightly enlarged until the values on the upper bounds are also inside. If all the values are identical, the radius starts from `min_br`.
+    ///
+    /// Return `None` if there are no values.
+    pub fn new_enclosing<'o>(
+        vs: impl Iterator<Item = &'o [F; D]> + Clone,
+        min_br: F,
+    ) -> Option<Self> {
+        let mut lower = [F::ZERO; D];
+        let mut upper = [F::ZERO; D];
+        let mut is_empty = true;
+        for v in vs.clone() {
+            for d in 0..D {
+                assert!(
+                    v[d].is_finite(),
+                    "A value does not seem finite when calculating the enclosing bounding box..."
+                );
+                if is_empty || v[d] < lower[d] {
+                    lower[d] = v[d];
+                }
+                if is_empty || v[d] > upper[d] {
+                    upper[d] = v[d];
+                }
+            }
+            is_empty = false;
+        }
+        if is_empty {
+            return None;
+        }
+
+        let mut bc = [F::ZERO; D];
+        let mut br = F::ZERO;
+        let mut step = F::ZERO;
+        for d in 0..D {
+            bc[d] = lower[d] * F::HALF + upper[d] * F::HALF;
+            let curr_br = (upper[d] - lower[d]) * F::HALF;
+            if curr_br > br {
+                br = curr_br;
+            }
+            if bc[d].abs() > step {
+                step = bc[d].abs();
+            }
+        }
+        if br <= F::ZERO {
+            br = min_br;
+        }
+        if br > step {
+            step = br;
+        }
+        step *= F::EPSILON;
+
+        let mut ans = Self::new_with_arr(&bc, br);
+        while !vs
+            .clone()
+            .all(|v| ans.is_containing(&ColVec::new_with_arr(v)))
+        {
+            ans.br += step;
+            step *= F::TWO;
+            assert!(
+                ans.br.is_finite(),
+                "The bounding box radius seem to become not finite during enclosing..."
+            );
+        }
+        Some(ans)
+    }
+
     #[inline]
-    pub fn calc_next_dir(&self, vc: &ColVec<D>) -> usize {
+    pub fn calc_next_dir(&self, vc: &ColVec<D, F>) -> usize {
         let m: usize = 1 << (Self::DIM - 1);
         let mut ans = 0;
         for d in 0..D {
@@ -36,7 +103,7 @@ impl<const D: Udim> BoundBox<D> {
     #[inline]
     pub fn calc_child_bb(&self, i: &usize) -> Self {
         let mut ans_bc = self.bc.clone();
-        let ans_br = self.br * 0.5;
+        let ans_br = self.br * F::HALF;
 
         let mask: usize = 1 << (Self::DIM - 1);
         for d in 0..D {
@@ -45,9 +112,9 @@ impl<const D: Udim> BoundBox<D> {
         Self::new_with_arr(&ans_bc.data, ans_br)
     }
 
-    pub fn calc_reverse_expand_bb(&self, vc: &ColVec<D>) -> (Self, usize) {
+    pub fn calc_reverse_expand_bb(&self, vc: &ColVec<D, F>) -> (Self, usize) {
         let mut ans_bc = self.bc.clone();
-        let mut ans_br = self.br.clone();
+        let mut ans_br = self.br;
         let mask: usize = 1 << (Self::DIM - 1);
 
         let mut dir = 0;
@@ -60,11 +127,11 @@ impl<const D: Udim> BoundBox<D> {
                 dir |= mask >> d;
             }
         }
-        ans_br *= 2.0;
+        ans_br *= F::TWO;
         (Self::new_with_arr(&ans_bc.data, ans_br), dir)
     }
 
-    pub fn self_expand(&mut self, vc: &ColVec<D>) {
+    pub fn self_expand(&mut self, vc: &ColVec<D, F>) {
         for d in 0..D {
             let curr_v = &mut self.bc.data[d];
             if vc.data[d] >= *curr_v {
@@ -77,14 +144,14 @@ impl<const D: Udim> BoundBox<D> {
                 "The bounding box center seem to become not finite during expanding..."
             )
         }
-        self.br *= 2.0;
+        self.br *= F::TWO;
         assert!(
             self.br.is_finite(),
             "The bounding box radius seem to become not finite during expanding..."
         )
     }
 
-    pub fn is_containing(&self, vc: &ColVec<D>) -> bool {
+    pub fn is_containing(&self, vc: &ColVec<D, F>) -> bool {
         let r = self.br;
         for d in 0..D {
             let curr_c = self.bc.data[d];
@@ -96,10 +163,74 @@ impl<const D: Udim> BoundBox<D> {
         true
     }
 
+    /// Calculate the margin covering the rounding errors of the bounds.
+    ///
+    /// A child's bounds are calculated from its parent's center and radius, so they can be a few rounding errors away from the bounds the values are divided with. A query should only rely on a hypercube enlarged by this margin.
+    #[inline]
+    fn calc_rounding_margin(&self) -> F {
+        let mut ans = self.br;
+        for d in 0..D {
+            if self.bc.data[d].abs() > ans {
+                ans = self.bc.data[d].abs();
+            }
+        }
+        ans * F::EPSILON * F::from_f64(64.0)
+    }
+
+    /// Calculate the squared distance from a point to the nearest point of the hypercube, which is zero for a point inside.
+    ///
+    /// The hypercube is enlarged by the rounding margin, so the distance never exceeds the distance to a value inside.
+    #[inline]
+    pub fn calc_min_dis_pow2(&self, v: &[F; D]) -> F {
+        let br = self.br + self.calc_rounding_margin();
+        let mut ans = F::ZERO;
+        for (v_d, bc_d) in v.iter().zip(self.bc.data.iter()) {
+            let diff = (*v_d - *bc_d).abs() - br;
+            if diff > F::ZERO {
+                ans += diff * diff;
+            }
+        }
+        ans
+    }
+
+    /// Calculate the squared distance between the nearest points of two hypercubes, which is zero for two touching or overlapping hypercubes.
+    ///
+    /// Both hypercubes are enlarged by their rounding margins.
+    #[inline]
+    pub fn calc_min_dis_pow2_to_bb(&self, other: &Self) -> F {
+        let br = self.br + self.calc_rounding_margin() + other.br + other.calc_rounding_margin();
+        let mut ans = F::ZERO;
+        for d in 0..D {
+            let diff = (self.bc.data[d] - other.bc.data[d]).abs() - br;
+            if diff > F::ZERO {
+                ans += diff * diff;
+            }
+        }
+        ans
+    }
+
+    /// Check whether all the values in the hypercube are inside the axis-aligned box from `min` to `max` (both inclusive).
+    ///
+    /// The hypercube is enlarged by the rounding margin.
+    #[inline]
+    pub fn is_inside_aabb(&self, min: &[F; D], max: &[F; D]) -> bool {
+        let br = self.br + self.calc_rounding_margin();
+        (0..D).all(|d| self.bc.data[d] - br >= min[d] && self.bc.data[d] + br <= max[d])
+    }
+
+    /// Check whether the hypercube cannot contain any value in the axis-aligned box from `min` to `max` (both inclusive).
+    ///
+    /// The hypercube is enlarged by the rounding margin.
+    #[inline]
+    pub fn is_disjoint_from_aabb(&self, min: &[F; D], max: &[F; D]) -> bool {
+        let br = self.br + self.calc_rounding_margin();
+        (0..D).any(|d| self.bc.data[d] + br < min[d] || self.bc.data[d] - br > max[d])
+    }
+
     #[inline]
     pub fn set_self_from_parent_bb_and_dir(&mut self, parent_bb: &Self, dir: usize) {
         self.bc.clone_from(&parent_bb.bc);
-        let ans_r = parent_bb.br * 0.5;
+        let ans_r = parent_bb.br * F::HALF;
 
         let mask: usize = 1 << (Self::DIM - 1);
         for d in 0..D {
@@ -168,9 +299,74 @@ mod test {
         let vc0 = ColVec::new_with_arr(&[3.0, 0.0, 0.0]);
         let vc1 = ColVec::new_with_arr(&[0.0, 1.0, 0.0]);
         let vc2 = ColVec::new_with_arr(&[0.0, 3.0, 0.0]);
-        assert_eq!(bb.is_containing(&vc0), false);
-        assert_eq!(bb.is_containing(&vc1), true);
-        assert_eq!(bb.is_containing(&vc2), false);
+        assert!(!bb.is_containing(&vc0));
+        assert!(bb.is_containing(&vc1));
+        assert!(!bb.is_containing(&vc2));
+    }
+
+    #[test]
+    fn check_calc_min_dis_pow2() {
+        let bb = BoundBox::new_with_arr(&[0.0, 0.0], 2.0);
+        assert_eq!(bb.calc_min_dis_pow2(&[1.0, -1.0]), 0.0);
+        assert_eq!(bb.calc_min_dis_pow2(&[2.0, 0.0]), 0.0);
+        // The rounding margin makes the distance to a point outside slightly shorter.
+        assert!(bb.calc_min_dis_pow2(&[5.0, 0.0]) <= 9.0);
+        assert!((bb.calc_min_dis_pow2(&[5.0, 0.0]) - 9.0).abs() < 1e-12);
+        assert!((bb.calc_min_dis_pow2(&[-5.0, 6.0]) - (9.0 + 16.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn check_calc_min_dis_pow2_on_rounded_child_bound() {
+        // The value on the parent's center is divided into the upper child, whose lower bound is rounded up above the value.
+        let parent_bb = BoundBox::new_with_arr(&[0.7, 0.0], 1.3);
+        let child_bb =
+            parent_bb.calc_child_bb(&parent_bb.calc_next_dir(&ColVec::new_with_arr(&[0.7, 0.0])));
+        assert!(child_bb.bc.data[0] - child_bb.br > 0.7);
+
+        assert_eq!(child_bb.calc_min_dis_pow2(&[0.7, 0.0]), 0.0);
+        let ulp = f64::EPSILON / 2.0;
+        assert!(child_bb.calc_min_dis_pow2(&[0.7 - ulp, 0.0]) <= ulp * ulp);
+    }
+
+    #[test]
+    fn check_calc_min_dis_pow2_to_bb() {
+        let bb = BoundBox::new_with_arr(&[0.0, 0.0], 1.0);
+        let other = BoundBox::new_with_arr(&[1.5, 0.5], 0.5);
+        assert_eq!(bb.calc_min_dis_pow2_to_bb(&other), 0.0);
+        let other = BoundBox::new_with_arr(&[4.5, -5.5], 0.5);
+        assert!((bb.calc_min_dis_pow2_to_bb(&other) - (9.0 + 16.0)).abs() < 1e-9);
+        assert!((other.calc_min_dis_pow2_to_bb(&bb) - (9.0 + 16.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn check_aabb_relations() {
+        let bb = BoundBox::new_with_arr(&[0.0, 0.0], 1.0);
+        assert!(bb.is_inside_aabb(&[-1.5, -1.5], &[1.5, 1.5]));
+        assert!(!bb.is_inside_aabb(&[-1.0, -1.0], &[1.0, 1.0]));
+        assert!(!bb.is_inside_aabb(&[-1.5, -0.5], &[1.5, 1.5]));
+        assert!(!bb.is_disjoint_from_aabb(&[-0.5, -0.5], &[0.5, 0.5]));
+        assert!(!bb.is_disjoint_from_aabb(&[-2.0, -2.0], &[-1.0, -1.0]));
+        assert!(!bb.is_disjoint_from_aabb(&[1.0, 0.0], &[2.0, 1.0]));
+        assert!(bb.is_disjoint_from_aabb(&[1.5, 0.0], &[2.0, 1.0]));
+        assert!(bb.is_disjoint_from_aabb(&[0.0, -3.0], &[1.0, -1.5]));
+    }
+
+    #[test]
+    fn check_new_enclosing() {
+        let vs = [[1.0, -3.0], [3.0, 5.0], [-1.0, 1.0]];
+        let bb = BoundBox::new_enclosing(vs.iter(), 1e-8).unwrap();
+        assert_eq!(bb.bc.data, [1.0, 1.0]);
+        assert!(bb.br > 4.0 && bb.br < 4.0 + 1e-12);
+        for v in vs.iter() {
+            assert!(bb.is_containing(&ColVec::new_with_arr(v)));
+        }
+
+        let vs = [[0.1, 0.2], [0.1, 0.2]];
+        let bb = BoundBox::new_enclosing(vs.iter(), 1e-8).unwrap();
+        assert!(bb.br >= 1e-8 && bb.is_containing(&ColVec::new_with_arr(&vs[0])));
+
+        let vs: [[f64; 2]; 0] = [];
+        assert_eq!(BoundBox::new_enclosing(vs.iter(), 1e-8), None);
     }
 
     #[test]
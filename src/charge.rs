@@ -0,0 +1,119 @@
+use crate::{colvec::ColVec, Fnum, Udim};
+
+/// # Signed charge aggregates of a node
+///
+/// A single charge-weighted center breaks down when positive and negative charges cancel each other out, so the positive and the negative charges are aggregated separately. Each side keeps its charge-weighted center, its total charge, and the number of values contributing to it. The negative total is stored as a negative number.
+#[derive(Debug, PartialEq)]
+pub struct Charges<const D: Udim, F: Fnum> {
+    pub(crate) pos_vc: ColVec<D, F>,
+    pub(crate) pos_q: F,
+    pos_count: usize,
+
+    pub(crate) neg_vc: ColVec<D, F>,
+    pub(crate) neg_q: F,
+    neg_count: usize,
+}
+
+impl<const D: Udim, F: Fnum> Charges<D, F> {
+    #[inline]
+    pub fn new_zeros() -> Self {
+        Self {
+            pos_vc: ColVec::new_zeros(),
+            pos_q: F::ZERO,
+            pos_count: 0,
+            neg_vc: ColVec::new_zeros(),
+            neg_q: F::ZERO,
+            neg_count: 0,
+        }
+    }
+
+    #[inline]
+    pub fn add_charge(&mut self, v: &ColVec<D, F>, q: F) {
+        if q > F::ZERO {
+            self.pos_vc
+                .update_online_average_with_one_new_data(self.pos_q, &v.data, q);
+            self.pos_q += q;
+            self.pos_count += 1;
+        } else if q < F::ZERO {
+            self.neg_vc
+                .update_online_average_with_one_new_data(-self.neg_q, &v.data, -q);
+            self.neg_q += q;
+            self.neg_count += 1;
+        }
+    }
+
+    #[inline]
+    pub fn sub_charge(&mut self, v: &ColVec<D, F>, q: F) {
+        if q > F::ZERO {
+            self.pos_vc.update_online_average_with_one_data_removal(
+                self.pos_count,
+                self.pos_q,
+                &v.data,
+                q,
+            );
+            self.pos_count -= 1;
+            self.pos_q = if self.pos_count > 0 {
+                self.pos_q - q
+            } else {
+                F::ZERO
+            };
+        } else if q < F::ZERO {
+            self.neg_vc.update_online_average_with_one_data_removal(
+                self.neg_count,
+                -self.neg_q,
+                &v.data,
+                -q,
+            );
+            self.neg_count -= 1;
+            self.neg_q = if self.neg_count > 0 {
+                self.neg_q - q
+            } else {
+                F::ZERO
+            };
+        }
+    }
+}
+
+impl<const D: Udim, F: Fnum> Clone for Charges<D, F> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            pos_vc: self.pos_vc.clone(),
+            pos_q: self.pos_q,
+            pos_count: self.pos_count,
+            neg_vc: self.neg_vc.clone(),
+            neg_q: self.neg_q,
+            neg_count: self.neg_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_add_and_sub_charges() {
+        let mut charges: Charges<2, f64> = Charges::new_zeros();
+        let v0 = ColVec::new_with_arr(&[1.0, 0.0]);
+        let v1 = ColVec::new_with_arr(&[3.0, 0.0]);
+        let v2 = ColVec::new_with_arr(&[0.0, 2.0]);
+
+        charges.add_charge(&v0, 1.0);
+        charges.add_charge(&v1, 3.0);
+        charges.add_charge(&v2, -2.0);
+        assert_eq!(charges.pos_vc.data, [2.5, 0.0]);
+        assert_eq!(charges.pos_q, 4.0);
+        assert_eq!(charges.neg_vc.data, [0.0, 2.0]);
+        assert_eq!(charges.neg_q, -2.0);
+
+        charges.sub_charge(&v1, 3.0);
+        assert_eq!(charges.pos_vc.data, [1.0, 0.0]);
+        assert_eq!(charges.pos_q, 1.0);
+
+        charges.sub_charge(&v2, -2.0);
+        charges.add_charge(&v2, 0.0);
+        assert_eq!(charges.neg_vc.data, [0.0, 0.0]);
+        assert_eq!(charges.neg_q, 0.0);
+    }
+}
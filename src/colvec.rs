@@ -1,20 +1,21 @@
 use crate::{Fnum, Udim};
 
 #[derive(Debug, PartialEq)]
-pub struct ColVec<const D: Udim> {
-    pub data: [Fnum; D],
+pub struct ColVec<const D: Udim, F: Fnum> {
+    pub data: [F; D],
 }
 
-impl<const D: Udim> ColVec<D> {
+impl<const D: Udim, F: Fnum> ColVec<D, F> {
     #[inline]
     pub fn new_zeros() -> Self {
-        let data: [Fnum; D] = [0.0; D];
+        let data: [F; D] = [F::ZERO; D];
         Self { data }
     }
 
     #[inline]
-    pub fn new_with_arr(arr: &[Fnum; D]) -> Self {
-        let data: [Fnum; D] = arr.clone();
+    #[allow(clippy::needless_range_loop)]
+    pub fn new_with_arr(arr: &[F; D]) -> Self {
+        let data: [F; D] = *arr;
         for d in 0..D {
             assert!(data[d].is_finite(), "A numeric error occurred...");
         }
@@ -22,7 +23,8 @@ impl<const D: Udim> ColVec<D> {
     }
 
     #[inline]
-    pub fn clone_from_arr_ref(&mut self, arr_ref: &[Fnum; D]) {
+    #[allow(clippy::needless_range_loop)]
+    pub fn clone_from_arr_ref(&mut self, arr_ref: &[F; D]) {
         for d in 0..D {
             self.data[d].clone_from(&arr_ref[d]);
             assert!(
@@ -33,16 +35,17 @@ impl<const D: Udim> ColVec<D> {
     }
 
     #[inline]
+    #[allow(clippy::needless_range_loop)]
     pub fn update_online_average_with_one_new_data(
         &mut self,
-        curr_self_count: usize,
-        other: &[Fnum; D],
+        curr_self_weight: F,
+        other: &[F; D],
+        other_weight: F,
     ) {
-        let curr_self_count = curr_self_count as Fnum;
-        let next_self_count = curr_self_count + 1.0;
+        let next_self_weight = curr_self_weight + other_weight;
         for i in 0..D {
-            self.data[i] =
-                self.data[i] * (curr_self_count / next_self_count) + other[i] / next_self_count;
+            self.data[i] = self.data[i] * (curr_self_weight / next_self_weight)
+                + other[i] * other_weight / next_self_weight;
             assert!(
                 self.data[i].is_finite(),
                 "A numeric error occurred when calculating the new average value after node adding..."
@@ -51,32 +54,32 @@ impl<const D: Udim> ColVec<D> {
     }
 
     #[inline]
+    #[allow(clippy::needless_range_loop)]
     pub fn update_online_average_with_one_data_removal(
         &mut self,
         curr_self_count: usize,
-        other: &[Fnum; D],
+        curr_self_weight: F,
+        other: &[F; D],
+        other_weight: F,
     ) {
-        let curr_self_count = curr_self_count as Fnum;
-        let prev_self_count = curr_self_count - 1.0;
-        if prev_self_count == 0.0 {
+        let prev_self_weight = curr_self_weight - other_weight;
+        if curr_self_count <= 1 {
             for i in 0..D {
-                self.data[i] = 0.0;
+                self.data[i] = F::ZERO;
             }
         } else {
             for i in 0..D {
-                self.data[i] = (self.data[i] - other[i] / curr_self_count)
-                    * (curr_self_count / prev_self_count);
+                self.data[i] = (self.data[i] - other[i] * other_weight / curr_self_weight)
+                    * (curr_self_weight / prev_self_weight);
                 assert!(self.data[i].is_finite(), "A numeric error occurred when calculating the new average value after node removal...");
             }
         }
     }
 }
 
-impl<const D: Udim> Clone for ColVec<D> {
+impl<const D: Udim, F: Fnum> Clone for ColVec<D, F> {
     #[inline]
     fn clone(&self) -> Self {
-        Self {
-            data: self.data.clone(),
-        }
+        Self { data: self.data }
     }
 }
@@ -0,0 +1,48 @@
+use crate::{Fnum, Udim};
+
+/// # The summary of a node considered as a super node
+///
+/// The summary is what an [OpeningCriterion] knows about a node: its bounding hypercube, the average position, the number, and the total weight of the values inside.
+pub struct NodeSummary<'o, const D: Udim, F: Fnum> {
+    /// The center of the node's bounding hypercube.
+    pub bc: &'o [F; D],
+    /// The half width of the node's bounding hypercube.
+    pub br: F,
+    /// The average position of the values inside, weighted if the values have weights.
+    pub vc: &'o [F; D],
+    /// The number of values inside.
+    pub count: usize,
+    /// The total weight of the values inside.
+    pub weight: F,
+    /// The weighted sum of the squared distances from the values inside to the average position, `sum(w * |v - vc|^2)`, which is the trace of the second central moment. It is only tracked with the `quadrupole` feature, and it is `None` otherwise.
+    pub b2: Option<F>,
+}
+
+impl<'o, const D: Udim, F: Fnum> NodeSummary<'o, D, F> {
+    /// Calculate the max distance from the average position of the values to the corners of the bounding hypercube, which bounds the distance to any value inside.
+    pub fn calc_bmax(&self) -> F {
+        let mut ans_pow2 = F::ZERO;
+        for d in 0..D {
+            let diff = (self.vc[d] - self.bc[d]).abs() + self.br;
+            ans_pow2 += diff * diff;
+        }
+        ans_pow2.sqrt()
+    }
+}
+
+/// # The criterion deciding whether a node is a super node
+///
+/// A super node is far enough from the target to interact as a whole, with its average position and the number (or the total weight) of values inside. Otherwise, the node is opened, and its children or values are considered one by one.
+///
+/// Any closure `Fn(&[F; D], &[F; D], F) -> bool` taking the position of the target, the average position of the values in the node, and the half width of the node is an opening criterion. Wrap a closure written inline with [crate::utils::criterion_fn] to infer its argument types. The [crate::utils] module provides more criteria using the full [NodeSummary].
+pub trait OpeningCriterion<const D: Udim, F: Fnum> {
+    /// Return `true` if the node is a super node for the target at `curr_v_ref`.
+    fn is_super_node(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>) -> bool;
+}
+
+impl<const D: Udim, F: Fnum, C: Fn(&[F; D], &[F; D], F) -> bool> OpeningCriterion<D, F> for C {
+    #[inline]
+    fn is_super_node(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>) -> bool {
+        self(curr_v_ref, node.vc, node.br)
+    }
+}
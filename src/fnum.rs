@@ -0,0 +1,90 @@
+use std::{
+    fmt::Debug,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+/// # The floating-point number type of the values' coordinates
+///
+/// The tree, the nodes, and the helper closures in [crate::utils] are generic over this trait, so the tree can store `f32` coordinates directly instead of converting every position to and from `f64`. The trait is implemented for `f32` and `f64`.
+///
+/// The trait only contains the constants and the methods the tree needs, which are the same as the primitive types' inherent ones.
+pub trait Fnum:
+    Copy
+    + Debug
+    + PartialEq
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Send
+    + Sync
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const TWO: Self;
+    const HALF: Self;
+    const EPSILON: Self;
+
+    /// Convert a `f64` number (usually a constant) into this type, rounding if needed.
+    fn from_f64(v: f64) -> Self;
+
+    /// Convert a count into this type, rounding if needed.
+    fn from_usize(v: usize) -> Self;
+
+    fn is_finite(self) -> bool;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+}
+
+macro_rules! impl_fnum {
+    ($t:ty) => {
+        impl Fnum for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const TWO: Self = 2.0;
+            const HALF: Self = 0.5;
+            const EPSILON: Self = <$t>::EPSILON;
+
+            #[inline]
+            fn from_f64(v: f64) -> Self {
+                v as $t
+            }
+
+            #[inline]
+            fn from_usize(v: usize) -> Self {
+                v as $t
+            }
+
+            #[inline]
+            fn is_finite(self) -> bool {
+                <$t>::is_finite(self)
+            }
+
+            #[inline]
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+
+            #[inline]
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            #[inline]
+            fn powi(self, n: i32) -> Self {
+                <$t>::powi(self, n)
+            }
+        }
+    };
+}
+
+impl_fnum!(f32);
+impl_fnum!(f64);
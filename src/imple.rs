@@ -2,7 +2,7 @@ use crate::{
     boundbox::BoundBox,
     colvec::ColVec,
     nodes::{Internal, Leaf, NodeIndex},
-    BarnesHutTree, Fnum, Udim,
+    BarnesHutTree, Fnum, Udim, ValueEntry,
 };
 
 #[inline]
@@ -45,31 +45,46 @@ pub(crate) fn get_ref_from_arr_ref<'o, O, T: AsRef<O>>(
 
 mod add;
 
+mod build;
+
 mod sub;
 
 mod calc;
 
-impl<const D: Udim> BarnesHutTree<D> {
+mod dual;
+
+mod exclude;
+
+mod query;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     #[inline]
     pub(crate) fn new_without_add(
-        root_bc: &[Fnum; D],
-        root_br: Fnum,
-        vals: &[[Fnum; D]],
-        br_limit: Fnum,
+        root_bc: &[F; D],
+        root_br: F,
+        vals: &[[F; D]],
+        br_limit: F,
     ) -> Self {
         let len = vals.len();
-        let mut vs: Vec<Box<(ColVec<D>, Option<(usize, usize)>)>> = Vec::with_capacity(len);
+        let mut vs: Vec<Box<ValueEntry<D, F>>> = Vec::with_capacity(len);
         let leaf_vec = Vec::with_capacity(len);
         let internal_vec = Vec::with_capacity(len);
         for val in vals {
             vs.push(Box::new((ColVec::new_with_arr(val), None)));
         }
+        let ws = vec![F::ONE; len];
+        let qs = vec![F::ZERO; len];
         assert!(
-            br_limit.is_finite() && br_limit > 0.0,
+            br_limit.is_finite() && br_limit > F::ZERO,
             "The limit should be finite and greater than zero."
         );
         Self {
             vs,
+            ws,
+            qs,
             leaf_vec,
             internal_vec,
             root: None,
@@ -79,7 +94,7 @@ impl<const D: Udim> BarnesHutTree<D> {
     }
 
     #[inline]
-    pub(crate) fn new_leaf(&mut self, leaf_box: Box<Leaf<D>>) -> usize {
+    pub(crate) fn new_leaf(&mut self, leaf_box: Box<Leaf<D, F>>) -> usize {
         let ans_i = self.leaf_vec.len();
         self.leaf_vec.push(leaf_box);
         ans_i
@@ -127,7 +142,7 @@ impl<const D: Udim> BarnesHutTree<D> {
     }
 
     #[inline]
-    pub(crate) fn new_internal(&mut self, internal_box: Box<Internal<D>>) -> usize {
+    pub(crate) fn new_internal(&mut self, internal_box: Box<Internal<D, F>>) -> usize {
         let ans_i = self.internal_vec.len();
         self.internal_vec.push(internal_box);
         ans_i
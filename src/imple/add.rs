@@ -1,11 +1,11 @@
-use crate::{BarnesHutTree, Udim};
+use crate::{BarnesHutTree, Fnum, Udim};
 
 use super::get_mut_ref_from_arr_mut_ref;
 
 mod expand_root;
 mod find_pointer_to_add;
 
-impl<const D: Udim> BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     /// # Add a node into the tree
     pub(crate) fn add(&mut self, value_i: usize) {
         self.expand_root(value_i);
@@ -15,7 +15,12 @@ impl<const D: Udim> BarnesHutTree<D> {
         let leaf_mut_ref =
             get_mut_ref_from_arr_mut_ref(&mut self.leaf_vec, leaf_i, "To add value into the leaf");
 
-        let id = leaf_mut_ref.add_value(value_i, &self.vs[value_i].0);
+        let id = leaf_mut_ref.add_value(
+            value_i,
+            &self.vs[value_i].0,
+            self.ws[value_i],
+            self.qs[value_i],
+        );
 
         self.vs[value_i].1 = Some((leaf_i, id));
     }
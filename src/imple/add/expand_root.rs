@@ -4,10 +4,10 @@ use crate::{
         Internal,
         NodeIndex::{self, In, Le},
     },
-    BarnesHutTree, ColVec, Udim,
+    BarnesHutTree, ColVec, Fnum, Udim,
 };
 
-impl<const D: Udim> BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     #[inline]
     fn expand_struct_bb(&mut self, value_i: usize) {
         let vc = &get_ref_from_arr_ref(&self.vs, value_i, "For updating struct bb").0;
@@ -18,7 +18,7 @@ impl<const D: Udim> BarnesHutTree<D> {
     }
 
     #[inline]
-    fn expand_root_internal(&mut self, mut root_i: usize, vc: &ColVec<D>) -> usize {
+    fn expand_root_internal(&mut self, mut root_i: usize, vc: &ColVec<D, F>) -> usize {
         while !get_mut_ref_from_arr_mut_ref(
             self.internal_vec.as_mut(),
             root_i,
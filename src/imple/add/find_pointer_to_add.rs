@@ -4,10 +4,10 @@ use crate::{
         Internal, Leaf,
         NodeIndex::{self, In, Le},
     },
-    BarnesHutTree, Udim,
+    BarnesHutTree, Fnum, Udim,
 };
 
-impl<const D: Udim> BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     /// # Find the pointer to add the leaf node
     ///
     /// We need to find the correct position to add a leaf position.
@@ -71,7 +71,7 @@ impl<const D: Udim> BarnesHutTree<D> {
 
             let value_ref = &get_ref_from_arr_ref(&self.vs, value_i, "Getting the to-add value").0;
 
-            target_internal.add_value(value_ref);
+            target_internal.add_value(value_ref, self.ws[value_i], self.qs[value_i]);
 
             let next_dir = target_internal.calc_next_dir(value_ref);
 
@@ -80,7 +80,7 @@ impl<const D: Udim> BarnesHutTree<D> {
             } else {
                 &mut self.root
             } = Some(NodeIndex::In(target_internal_i));
-            prev_internal = Some((target_internal_i, next_dir.clone()));
+            prev_internal = Some((target_internal_i, next_dir));
         }
 
         if let Some((parent_internal_i, from_dir)) = prev_internal {
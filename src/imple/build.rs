@@ -0,0 +1,160 @@
+use crate::{
+    boundbox::BoundBox,
+    imple::{get_mut_ref_from_arr_mut_ref, get_ref_from_arr_ref},
+    nodes::{Internal, Leaf, NodeIndex},
+    BarnesHutTree, Fnum, Udim,
+};
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    /// # Add all the values into the empty tree
+    ///
+    /// The bounding box is first expanded over the values in the order of their indices, the same way `expand_root` doubles the root when adding them one by one, so it ends at the same hypercube. Then, the tree is built in bulk.
+    pub(crate) fn add_all(&mut self) {
+        for v in self.vs.iter() {
+            while !self.bb.is_containing(&v.0) {
+                self.bb.self_expand(&v.0);
+            }
+        }
+        self.build();
+    }
+
+    /// # Build the tree from all the values at once
+    ///
+    /// Instead of walking from the root for every value, the values' indices are sorted into the Z-order (Morton order) of the tree's hypercubes, level by level, by the same `calc_next_dir` the tree uses when adding a value. In each hypercube, the indices are stably partitioned into the child directions, so a continuous range of the sorted indices always belongs to one node. The nodes are emitted in depth-first order, following the sorted indices.
+    ///
+    /// A hypercube with more than one value and a radius larger than the limit becomes an internal node. Otherwise, it becomes a leaf node. This is the same structure the one-by-one insertion ends up with.
+    ///
+    /// The value centers are running averages, so the values are then added to the nodes from their leaves up to the root in the order of their indices, the same order the one-by-one insertion adds them in. Therefore, the nodes' aggregates are also identical to the one-by-one insertion's.
+    ///
+    /// The tree should not contain any nodes, and its bounding box should contain all the values before building.
+    pub(crate) fn build(&mut self) {
+        let len = self.vs.len();
+        if len == 0 {
+            return;
+        }
+
+        let mut sorted: Vec<usize> = (0..len).collect();
+        let mut sorted_buf: Vec<usize> = vec![0; len];
+        let mut dirs: Vec<usize> = vec![0; len];
+        let mut to_leafs: Vec<usize> = vec![0; len];
+
+        let dim_len = 2_usize.pow(D as u32);
+        let mut counts: Vec<usize> = vec![0; dim_len + 1];
+        let mut nexts: Vec<usize> = vec![0; dim_len];
+
+        // The range of the values to build a node with, and the node's parent and direction.
+        let mut stack = vec![(0, len, None)];
+
+        while let Some((start, end, parent_info)) = stack.pop() {
+            let bb = if let Some((parent_i, from_dir)) = parent_info {
+                get_ref_from_arr_ref(
+                    &self.internal_vec,
+                    parent_i,
+                    "Getting the parent to calculate the child bounding box",
+                )
+                .calc_child_bb(&from_dir)
+            } else {
+                self.bb.clone()
+            };
+
+            if end - start == 1 || bb.br <= self.br_limit {
+                let leaf_i = self.new_leaf(Leaf::new_empty_from_bb(bb));
+                self.link_built_node(parent_info, NodeIndex::Le(leaf_i));
+                for value_i in sorted[start..end].iter() {
+                    to_leafs[*value_i] = leaf_i;
+                }
+                continue;
+            }
+
+            let internal_i = self.new_internal(Internal::new_root(bb));
+            self.link_built_node(parent_info, NodeIndex::In(internal_i));
+
+            let internal_ref = &self.internal_vec[internal_i];
+            counts.fill(0);
+            for i in start..end {
+                let dir = internal_ref.calc_next_dir(&self.vs[sorted[i]].0);
+                dirs[i] = dir;
+                counts[dir + 1] += 1;
+            }
+            for dir in 0..dim_len {
+                counts[dir + 1] += counts[dir];
+            }
+
+            nexts.copy_from_slice(&counts[..dim_len]);
+            for i in start..end {
+                let dir = dirs[i];
+                sorted_buf[start + nexts[dir]] = sorted[i];
+                nexts[dir] += 1;
+            }
+            sorted[start..end].copy_from_slice(&sorted_buf[start..end]);
+
+            // Pushing in the reversed order to pop the children in the Z-order.
+            for dir in (0..dim_len).rev() {
+                if counts[dir] < counts[dir + 1] {
+                    stack.push((
+                        start + counts[dir],
+                        start + counts[dir + 1],
+                        Some((internal_i, dir)),
+                    ));
+                }
+            }
+        }
+
+        for (value_i, leaf_i) in to_leafs.into_iter().enumerate() {
+            let w = self.ws[value_i];
+            let q = self.qs[value_i];
+            let v = &self.vs[value_i].0;
+
+            let leaf_mut_ref = get_mut_ref_from_arr_mut_ref(
+                &mut self.leaf_vec,
+                leaf_i,
+                "To add value into the leaf",
+            );
+            let id = leaf_mut_ref.add_value(value_i, v, w, q);
+
+            let mut parent_info = leaf_mut_ref.parent;
+            while let Some((parent_i, _)) = parent_info {
+                let parent_mut_ref = get_mut_ref_from_arr_mut_ref(
+                    &mut self.internal_vec,
+                    parent_i,
+                    "To add value into the ancestor internal node",
+                );
+                parent_mut_ref.add_value(v, w, q);
+                parent_info = parent_mut_ref.parent;
+            }
+
+            self.vs[value_i].1 = Some((leaf_i, id));
+        }
+    }
+
+    /// # Rebuild the tree in bulk with a new bounding box
+    ///
+    /// All the nodes are dropped, and the tree is built again. The bounding box should contain all the values.
+    pub(crate) fn rebuild(&mut self, bb: BoundBox<D, F>) {
+        self.leaf_vec.clear();
+        self.internal_vec.clear();
+        self.root = None;
+        for v in self.vs.iter_mut() {
+            v.1 = None;
+        }
+        self.bb = bb;
+        self.build();
+    }
+
+    #[inline]
+    fn link_built_node(&mut self, parent_info: Option<(usize, usize)>, node_i: NodeIndex) {
+        if let Some((parent_i, from_dir)) = parent_info {
+            match node_i {
+                NodeIndex::In(internal_i) => {
+                    self.internal_vec[internal_i].parent = parent_info;
+                }
+                NodeIndex::Le(leaf_i) => {
+                    self.leaf_vec[leaf_i].parent = parent_info;
+                }
+            }
+            self.internal_vec[parent_i].nexts[from_dir] = Some(node_i);
+        } else {
+            self.root = Some(node_i);
+        }
+    }
+}
@@ -1,22 +1,119 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
 use crate::{
+    colvec::ColVec,
     nodes::{Internal, Leaf, NodeIndex},
-    BarnesHutTree, Fnum, Udim,
+    BarnesHutTree, Fnum, Kernel, NodeSummary, OpeningCriterion, Udim,
 };
 
 use super::get_ref_from_arr_ref;
 
-impl<const D: Udim> BarnesHutTree<D> {
+/// # What the target value is interacting with
+///
+/// During the traversal, the target value either interacts with a "far" super node as a whole or with another single value directly. The public calculation methods translate this into the arguments of their own closures.
+pub(crate) enum Interaction<'o, const D: Udim, F: Fnum> {
+    Internal(&'o Internal<D, F>),
+    Leaf(&'o Leaf<D, F>),
+    Value {
+        i: usize,
+        v: &'o ColVec<D, F>,
+        weight: F,
+        charge: F,
+    },
+}
+
+impl<'o, const D: Udim, F: Fnum> Interaction<'o, D, F> {
+    #[inline]
+    pub(crate) fn get_vc(&self) -> &'o [F; D] {
+        match self {
+            Interaction::Internal(internal_ref) => &internal_ref.vc.data,
+            Interaction::Leaf(leaf_ref) => &leaf_ref.vc.data,
+            Interaction::Value { v, .. } => &v.data,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_values_weight_inside(&self) -> F {
+        match self {
+            Interaction::Internal(internal_ref) => internal_ref.get_values_weight_inside(),
+            Interaction::Leaf(leaf_ref) => leaf_ref.get_values_weight_inside(),
+            Interaction::Value { weight, .. } => *weight,
+        }
+    }
+
+    /// Get the positive charge center, the positive total charge, the negative charge center, and the negative total charge.
+    #[inline]
+    pub(crate) fn get_charges(&self) -> (&'o [F; D], F, &'o [F; D], F) {
+        let charges = match self {
+            Interaction::Internal(internal_ref) => &internal_ref.charges,
+            Interaction::Leaf(leaf_ref) => &leaf_ref.charges,
+            Interaction::Value { v, charge, .. } => {
+                return if *charge >= F::ZERO {
+                    (&v.data, *charge, &v.data, F::ZERO)
+                } else {
+                    (&v.data, F::ZERO, &v.data, *charge)
+                };
+            }
+        };
+        (
+            &charges.pos_vc.data,
+            charges.pos_q,
+            &charges.neg_vc.data,
+            charges.neg_q,
+        )
+    }
+
+    /// Get the summary of a super node. A single value has none.
+    #[inline]
+    pub(crate) fn get_summary(&self) -> Option<NodeSummary<'o, D, F>> {
+        match self {
+            Interaction::Internal(internal_ref) => Some(internal_ref.get_summary()),
+            Interaction::Leaf(leaf_ref) => Some(leaf_ref.get_summary()),
+            Interaction::Value { .. } => None,
+        }
+    }
+
+    /// Calculate with a kernel: a single value is calculated exactly, and a node is approximated as a whole.
+    #[inline]
+    pub(crate) fn calc_with_kernel<T>(
+        &self,
+        curr_v_ref: &[F; D],
+        kernel: &impl Kernel<D, F, T>,
+        write_to: &mut T,
+    ) {
+        match self {
+            Interaction::Internal(internal_ref) => {
+                kernel.approx(curr_v_ref, &internal_ref.get_summary(), write_to)
+            }
+            Interaction::Leaf(leaf_ref) => {
+                kernel.approx(curr_v_ref, &leaf_ref.get_summary(), write_to)
+            }
+            Interaction::Value { i, v, .. } => kernel.exact(curr_v_ref, *i, &v.data, write_to),
+        }
+    }
+
+    /// Get the second central moment. A single value has a moment of zeros.
+    #[cfg(feature = "quadrupole")]
+    #[inline]
+    pub(crate) fn get_moment(&self) -> [[F; D]; D] {
+        match self {
+            Interaction::Internal(internal_ref) => internal_ref.moment.data,
+            Interaction::Leaf(leaf_ref) => leaf_ref.moment.data,
+            Interaction::Value { .. } => [[F::ZERO; D]; D],
+        }
+    }
+}
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     #[inline]
     pub(crate) fn calc_node<'o, T>(
         &'o self,
-        curr_v_ref: &[Fnum; D],
+        curr_v_ref: &[F; D],
         node_box_ref: &'o NodeIndex,
         q: &mut VecDeque<&'o NodeIndex>,
         write_to: &mut T,
-        calc_this: impl Fn(&[Fnum; D], &[Fnum; D], Fnum) -> bool,
-        calc_fn: impl Fn(&[Fnum; D], &[Fnum; D], usize, &mut T),
+        calc_this: &impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
     ) {
         match node_box_ref {
             NodeIndex::In(internal_i_ref) => self.calc_neighbour_internal(
@@ -24,14 +121,14 @@ impl<const D: Udim> BarnesHutTree<D> {
                 get_ref_from_arr_ref(&self.internal_vec, *internal_i_ref, "Calculate internal"),
                 q,
                 write_to,
-                &calc_this,
+                calc_this,
                 calc_fn,
             ),
             NodeIndex::Le(leaf_i_ref) => self.calc_neighbour_leaf(
                 curr_v_ref,
                 get_ref_from_arr_ref(&self.leaf_vec, *leaf_i_ref, "Calculate leaf"),
                 write_to,
-                &calc_this,
+                calc_this,
                 calc_fn,
             ),
         }
@@ -39,59 +136,105 @@ impl<const D: Udim> BarnesHutTree<D> {
 
     pub(crate) fn calc_neighbour_internal<'o, T>(
         &'o self,
-        curr_v_ref: &[Fnum; D],
-        internal_ref: &'o Internal<D>,
+        curr_v_ref: &[F; D],
+        internal_ref: &'o Internal<D, F>,
         q: &mut VecDeque<&'o NodeIndex>,
         write_to: &mut T,
-        calc_this: impl Fn(&[Fnum; D], &[Fnum; D], Fnum) -> bool,
-        mut calc_fn: impl FnMut(&[Fnum; D], &[Fnum; D], usize, &mut T),
+        calc_this: &impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
     ) {
-        if calc_this(curr_v_ref, &internal_ref.vc.data, internal_ref.bb.br) {
-            calc_fn(
-                curr_v_ref,
-                &internal_ref.vc.data,
-                internal_ref.get_values_num_inside(),
-                write_to,
-            );
+        if calc_this.is_super_node(curr_v_ref, &internal_ref.get_summary()) {
+            calc_fn(curr_v_ref, Interaction::Internal(internal_ref), write_to);
         } else {
-            for node_box_opt_ref in internal_ref.nexts.iter() {
-                if let Some(node_box_ref) = node_box_opt_ref {
-                    q.push_back(node_box_ref);
-                }
+            for node_box_ref in internal_ref.nexts.iter().flatten() {
+                q.push_back(node_box_ref);
             }
         }
     }
-    pub(crate) fn calc_neighbour_leaf<T>(
-        &self,
-        curr_v_ref: &[Fnum; D],
-        leaf_ref: &Leaf<D>,
+    pub(crate) fn calc_neighbour_leaf<'o, T>(
+        &'o self,
+        curr_v_ref: &[F; D],
+        leaf_ref: &'o Leaf<D, F>,
         write_to: &mut T,
-        calc_this: impl Fn(&[Fnum; D], &[Fnum; D], Fnum) -> bool,
-        calc_fn: impl Fn(&[Fnum; D], &[Fnum; D], usize, &mut T),
+        calc_this: &impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
     ) {
-        if calc_this(curr_v_ref, &leaf_ref.vc.data, leaf_ref.bb.br) {
-            calc_fn(
-                curr_v_ref,
-                &leaf_ref.vc.data,
-                leaf_ref.get_values_num_inside(),
-                write_to,
-            );
+        if calc_this.is_super_node(curr_v_ref, &leaf_ref.get_summary()) {
+            calc_fn(curr_v_ref, Interaction::Leaf(leaf_ref), write_to);
         } else {
             for value_i in leaf_ref.vs.iter().cloned() {
-                calc_fn(
-                    curr_v_ref,
-                    &get_ref_from_arr_ref(&self.vs, value_i, "Calculating direct in-leaf values due to the current leaf is not far enough").0.data,
-                    leaf_ref.get_values_num_inside(),
-                    write_to,
-                );
+                calc_fn(curr_v_ref, self.calc_value_interaction(value_i), write_to);
             }
         }
     }
 
-    pub(crate) fn calc_leaf_siblings_and_get_parent<T>(
+    #[inline]
+    pub(crate) fn calc_value_interaction(&self, value_i: usize) -> Interaction<'_, D, F> {
+        Interaction::Value {
+            i: value_i,
+            v: &get_ref_from_arr_ref(
+                &self.vs,
+                value_i,
+                "Calculating direct in-leaf values due to the current leaf is not far enough",
+            )
+            .0,
+            weight: self.ws[value_i],
+            charge: self.qs[value_i],
+        }
+    }
+
+    /// # Get the index of the node of an interaction
+    ///
+    /// A node is found in the children of its parent, or it is the root. A value interaction has no node.
+    pub(crate) fn get_interaction_node(
         &self,
+        interaction: &Interaction<'_, D, F>,
+    ) -> Option<NodeIndex> {
+        let parent = match interaction {
+            Interaction::Internal(internal_ref) => internal_ref.parent,
+            Interaction::Leaf(leaf_ref) => leaf_ref.parent,
+            Interaction::Value { .. } => return None,
+        };
+        match parent {
+            Some((internal_i, dir)) => {
+                get_ref_from_arr_ref(
+                    &self.internal_vec,
+                    internal_i,
+                    "Getting the parent of the interacting node",
+                )
+                .nexts[dir]
+            }
+            None => self.root,
+        }
+    }
+
+    /// # Get the nodes containing a value
+    ///
+    /// We climb from the value's leaf to the root, so the leaf comes first. The list is empty if the value is out of range or not in a leaf.
+    pub(crate) fn get_nodes_containing_value(&self, value_i: usize) -> Vec<NodeIndex> {
+        let mut nodes = Vec::new();
+        if let Some((leaf_i, _)) = self.vs.get(value_i).and_then(|v| v.1) {
+            nodes.push(NodeIndex::Le(leaf_i));
+            let mut curr_info =
+                get_ref_from_arr_ref(&self.leaf_vec, leaf_i, "Getting the leaf of the value")
+                    .parent;
+            while let Some((curr_internal_i, _)) = curr_info {
+                nodes.push(NodeIndex::In(curr_internal_i));
+                curr_info = get_ref_from_arr_ref(
+                    &self.internal_vec,
+                    curr_internal_i,
+                    "Climbing from the leaf of the value",
+                )
+                .parent;
+            }
+        }
+        nodes
+    }
+
+    pub(crate) fn calc_leaf_siblings_and_get_parent<'o, T>(
+        &'o self,
         value_i: usize,
-        mut calc_fn: impl FnMut(&[Fnum; D], &[Fnum; D], usize, &mut T),
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
         write_to: &mut T,
     ) -> Option<(usize, usize)> {
         let (curr_leaf_i, curr_in_leaf_i) =
@@ -114,13 +257,196 @@ impl<const D: Udim> BarnesHutTree<D> {
             }
             calc_fn(
                 curr_v_ref,
-                &get_ref_from_arr_ref(&self.vs, *other_value_i, "Getting same-leaf values")
-                    .0
-                    .data,
-                1,
+                self.calc_value_interaction(*other_value_i),
                 write_to,
             )
         }
         curr_leaf_ref.parent
     }
+
+    /// # Calculate the interactions on one value
+    ///
+    /// First, we calculate the values sharing the same leaf. Then, we climb from the leaf to the root and push every sibling node into the queue. Finally, we go through the queue to either accept a node as a super node or expand its children.
+    pub(crate) fn calc_interactions_on_value<'o, T>(
+        &'o self,
+        value_i: usize,
+        is_super_node: &impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
+        write_to: &mut T,
+    ) {
+        let mut q: VecDeque<&NodeIndex> = VecDeque::with_capacity(self.get_total_nodes_num() / 2);
+        self.calc_interactions_on_value_with_queue(
+            value_i,
+            is_super_node,
+            calc_fn,
+            write_to,
+            &mut q,
+        );
+    }
+
+    /// # Calculate the interactions on one value with a given queue
+    ///
+    /// Same as `calc_interactions_on_value`, but the queue is given, so that it can be reused when calculating many values.
+    pub(crate) fn calc_interactions_on_value_with_queue<'o, T>(
+        &'o self,
+        value_i: usize,
+        is_super_node: &impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
+        write_to: &mut T,
+        q: &mut VecDeque<&'o NodeIndex>,
+    ) {
+        q.clear();
+        let mut curr_info = self.calc_leaf_siblings_and_get_parent(value_i, &calc_fn, write_to);
+
+        let curr_v_ref = &self.vs[value_i].0.data;
+
+        while let Some((curr_internal_i, curr_in_leaf_i)) = curr_info {
+            let curr_internal_ref = self.internal_vec[curr_internal_i].as_ref();
+            for (in_leaf_i, node_opt) in curr_internal_ref.nexts.iter().enumerate() {
+                if in_leaf_i == curr_in_leaf_i {
+                    continue;
+                }
+                if let Some(curr_node_box_ref) = node_opt.as_ref() {
+                    self.calc_node(
+                        curr_v_ref,
+                        curr_node_box_ref,
+                        q,
+                        write_to,
+                        is_super_node,
+                        &calc_fn,
+                    )
+                }
+            }
+            curr_info = curr_internal_ref.parent;
+        }
+
+        while let Some(curr_node_box_ref) = q.pop_front() {
+            self.calc_node(
+                curr_v_ref,
+                curr_node_box_ref,
+                q,
+                write_to,
+                is_super_node,
+                &calc_fn,
+            )
+        }
+    }
+
+    /// # Calculate the interactions on all the values
+    ///
+    /// The leaves are visited in depth-first order, which is also the spatial Z-order, so the values calculated one after another are close to each other and go through similar nodes. The traversal queue and stack are shared by all the values.
+    ///
+    /// The `write_to` slice should have one slot for each value.
+    pub(crate) fn calc_interactions_on_all_values<'o, T>(
+        &'o self,
+        is_super_node: &impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
+        write_to: &mut [T],
+    ) {
+        let mut q: VecDeque<&NodeIndex> = VecDeque::with_capacity(self.get_total_nodes_num() / 2);
+        self.for_each_leaf_in_z_order(|leaf_ref| {
+            for value_i in leaf_ref.vs.iter().cloned() {
+                self.calc_interactions_on_value_with_queue(
+                    value_i,
+                    is_super_node,
+                    &calc_fn,
+                    &mut write_to[value_i],
+                    &mut q,
+                );
+            }
+        });
+    }
+
+    /// # Visit the leaves in depth-first order
+    ///
+    /// The children are visited in the order of their directions, so the leaves are visited in the spatial Z-order.
+    pub(crate) fn for_each_leaf_in_z_order<'o>(&'o self, mut leaf_fn: impl FnMut(&'o Leaf<D, F>)) {
+        let mut stack: Vec<&NodeIndex> = Vec::new();
+        if let Some(root_ref) = self.root.as_ref() {
+            stack.push(root_ref);
+        }
+        while let Some(node_ref) = stack.pop() {
+            match node_ref {
+                NodeIndex::In(internal_i) => {
+                    let internal_ref = get_ref_from_arr_ref(
+                        &self.internal_vec,
+                        *internal_i,
+                        "Getting the internal node to visit its children",
+                    );
+                    for next in internal_ref.nexts.iter().rev().flatten() {
+                        stack.push(next);
+                    }
+                }
+                NodeIndex::Le(leaf_i) => {
+                    leaf_fn(get_ref_from_arr_ref(
+                        &self.leaf_vec,
+                        *leaf_i,
+                        "Getting the leaf node to visit",
+                    ));
+                }
+            }
+        }
+    }
+
+    /// # Calculate the interactions on a point
+    ///
+    /// The point does not need to be a value in the tree, so we go through the tree from the root with the queue, either accepting a node as a super node or expanding its children.
+    ///
+    /// If a value is excluded, its leaf and the internal nodes above it contain it, so they are never accepted as super nodes. The excluded value is skipped in its leaf.
+    pub(crate) fn calc_interactions_on_point<'o, T>(
+        &'o self,
+        point: &[F; D],
+        exclude_value_i: Option<usize>,
+        is_super_node: &impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
+        write_to: &mut T,
+    ) {
+        let excluded_nodes: HashSet<NodeIndex> = exclude_value_i
+            .map(|value_i| {
+                self.get_nodes_containing_value(value_i)
+                    .into_iter()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut q: VecDeque<&NodeIndex> = VecDeque::with_capacity(self.get_total_nodes_num() / 2);
+        if let Some(root_ref) = self.root.as_ref() {
+            q.push_back(root_ref);
+        }
+
+        while let Some(curr_node_box_ref) = q.pop_front() {
+            match curr_node_box_ref {
+                NodeIndex::In(internal_i) if excluded_nodes.contains(curr_node_box_ref) => {
+                    let internal_ref = get_ref_from_arr_ref(
+                        &self.internal_vec,
+                        *internal_i,
+                        "Expanding the internal node containing the excluded value",
+                    );
+                    for node_box_ref in internal_ref.nexts.iter().flatten() {
+                        q.push_back(node_box_ref);
+                    }
+                }
+                NodeIndex::Le(leaf_i) if excluded_nodes.contains(curr_node_box_ref) => {
+                    let leaf_ref = get_ref_from_arr_ref(
+                        &self.leaf_vec,
+                        *leaf_i,
+                        "Calculating the leaf containing the excluded value",
+                    );
+                    for value_i in leaf_ref.vs.iter().cloned() {
+                        if Some(value_i) != exclude_value_i {
+                            calc_fn(point, self.calc_value_interaction(value_i), write_to);
+                        }
+                    }
+                }
+                _ => self.calc_node(
+                    point,
+                    curr_node_box_ref,
+                    &mut q,
+                    write_to,
+                    is_super_node,
+                    &calc_fn,
+                ),
+            }
+        }
+    }
 }
@@ -0,0 +1,354 @@
+use crate::{nodes::NodeIndex, BarnesHutTree, Fnum, SymmetricKernel, Udim};
+
+use super::{calc::Interaction, get_ref_from_arr_ref};
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    #[inline]
+    pub(crate) fn get_node_interaction(&self, node_box_ref: &NodeIndex) -> Interaction<'_, D, F> {
+        match node_box_ref {
+            NodeIndex::In(internal_i) => Interaction::Internal(get_ref_from_arr_ref(
+                &self.internal_vec,
+                *internal_i,
+                "Getting the internal node's aggregates",
+            )),
+            NodeIndex::Le(leaf_i) => Interaction::Leaf(get_ref_from_arr_ref(
+                &self.leaf_vec,
+                *leaf_i,
+                "Getting the leaf node's aggregates",
+            )),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_node_br(&self, node_box_ref: &NodeIndex) -> F {
+        match node_box_ref {
+            NodeIndex::In(internal_i) => {
+                get_ref_from_arr_ref(
+                    &self.internal_vec,
+                    *internal_i,
+                    "Getting the internal radius",
+                )
+                .bb
+                .br
+            }
+            NodeIndex::Le(leaf_i) => {
+                get_ref_from_arr_ref(&self.leaf_vec, *leaf_i, "Getting the leaf radius")
+                    .bb
+                    .br
+            }
+        }
+    }
+
+    /// # Walk through the pairs of nodes
+    ///
+    /// Starting from the pair of the root and itself, a pair of the same node is split into the pairs of its children, including each child with itself. A pair of two different nodes is either accepted as well-separated, or split by the larger node's children. When both nodes are leaves and not well-separated, the pair is a near pair.
+    ///
+    /// Each unordered pair of values is covered by exactly one far pair or one near pair, and each pair is visited once. A near pair of a leaf and itself is passed with the same leaf index twice.
+    pub(crate) fn walk_node_pairs<'o>(
+        &'o self,
+        is_well_separated: impl Fn(&'o NodeIndex, &'o NodeIndex) -> bool,
+        mut far_fn: impl FnMut(&'o NodeIndex, &'o NodeIndex),
+        mut near_fn: impl FnMut(usize, usize),
+    ) {
+        // The second node is `None` for a pair of a node and itself.
+        let mut stack: Vec<(&'o NodeIndex, Option<&'o NodeIndex>)> = Vec::new();
+        if let Some(root_ref) = self.root.as_ref() {
+            stack.push((root_ref, None));
+        }
+
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (NodeIndex::Le(leaf_i), None) => near_fn(*leaf_i, *leaf_i),
+                (NodeIndex::In(internal_i), None) => {
+                    let nexts = &get_ref_from_arr_ref(
+                        &self.internal_vec,
+                        *internal_i,
+                        "Splitting the pair of an internal node and itself",
+                    )
+                    .nexts;
+                    for (dir, node_box_ref) in nexts.iter().enumerate() {
+                        if let Some(node_box_ref) = node_box_ref.as_ref() {
+                            stack.push((node_box_ref, None));
+                            for other_ref in nexts[dir + 1..].iter().flatten() {
+                                stack.push((node_box_ref, Some(other_ref)));
+                            }
+                        }
+                    }
+                }
+                (node_a_ref, Some(node_b_ref)) => {
+                    if is_well_separated(node_a_ref, node_b_ref) {
+                        far_fn(node_a_ref, node_b_ref);
+                        continue;
+                    }
+                    match (node_a_ref, node_b_ref) {
+                        (NodeIndex::Le(leaf_a_i), NodeIndex::Le(leaf_b_i)) => {
+                            near_fn(*leaf_a_i, *leaf_b_i)
+                        }
+                        (NodeIndex::In(internal_a_i), NodeIndex::Le(_)) => {
+                            self.push_split_pairs(&mut stack, *internal_a_i, node_b_ref)
+                        }
+                        (NodeIndex::Le(_), NodeIndex::In(internal_b_i)) => {
+                            self.push_split_pairs(&mut stack, *internal_b_i, node_a_ref)
+                        }
+                        (NodeIndex::In(internal_a_i), NodeIndex::In(internal_b_i)) => {
+                            if self.get_node_br(node_a_ref) >= self.get_node_br(node_b_ref) {
+                                self.push_split_pairs(&mut stack, *internal_a_i, node_b_ref)
+                            } else {
+                                self.push_split_pairs(&mut stack, *internal_b_i, node_a_ref)
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn push_split_pairs<'o>(
+        &'o self,
+        stack: &mut Vec<(&'o NodeIndex, Option<&'o NodeIndex>)>,
+        to_split_internal_i: usize,
+        other_ref: &'o NodeIndex,
+    ) {
+        let to_split_ref = get_ref_from_arr_ref(
+            &self.internal_vec,
+            to_split_internal_i,
+            "Splitting the larger node of a pair",
+        );
+        for node_box_ref in to_split_ref.nexts.iter().flatten() {
+            stack.push((node_box_ref, Some(other_ref)));
+        }
+    }
+
+    /// # Calculate the interactions on all the values with the dual-tree walk
+    ///
+    /// A well-separated pair of nodes interacts once in each direction, between the nodes' aggregates, and the results are accumulated on the nodes. A near pair of leaves interacts value by value, writing to the values directly.
+    ///
+    /// Finally, the nodes' results are pushed down: every value merges the accumulated results of its leaf and all the internal nodes above it.
+    pub(crate) fn calc_interactions_dual_tree<'o, T: Clone>(
+        &'o self,
+        is_well_separated: impl Fn(&[F; D], F, &[F; D], F) -> bool,
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T),
+        merge_fn: impl Fn(&mut T, &T),
+        zero: T,
+        write_to: &mut [T],
+    ) {
+        let mut internal_accs: Vec<T> = vec![zero.clone(); self.internal_vec.len()];
+        let mut leaf_accs: Vec<T> = vec![zero.clone(); self.leaf_vec.len()];
+
+        self.walk_node_pairs(
+            |node_a_ref, node_b_ref| {
+                is_well_separated(
+                    self.get_node_interaction(node_a_ref).get_vc(),
+                    self.get_node_br(node_a_ref),
+                    self.get_node_interaction(node_b_ref).get_vc(),
+                    self.get_node_br(node_b_ref),
+                )
+            },
+            |node_a_ref, node_b_ref| {
+                let (a, b) = (
+                    self.get_node_interaction(node_a_ref),
+                    self.get_node_interaction(node_b_ref),
+                );
+                for (to_ref, from) in [(node_a_ref, b), (node_b_ref, a)] {
+                    let to_vc = self.get_node_interaction(to_ref).get_vc();
+                    let acc = match to_ref {
+                        NodeIndex::In(internal_i) => &mut internal_accs[*internal_i],
+                        NodeIndex::Le(leaf_i) => &mut leaf_accs[*leaf_i],
+                    };
+                    calc_fn(to_vc, from, acc);
+                }
+            },
+            |leaf_a_i, leaf_b_i| {
+                let leaf_a_vs = &self.leaf_vec[leaf_a_i].vs;
+                let leaf_b_vs = &self.leaf_vec[leaf_b_i].vs;
+                if leaf_a_i == leaf_b_i {
+                    for value_i in leaf_a_vs.iter().cloned() {
+                        for other_value_i in leaf_a_vs.iter().cloned() {
+                            if other_value_i != value_i {
+                                calc_fn(
+                                    &self.vs[value_i].0.data,
+                                    self.calc_value_interaction(other_value_i),
+                                    &mut write_to[value_i],
+                                );
+                            }
+                        }
+                    }
+                    return;
+                }
+                for value_a_i in leaf_a_vs.iter().cloned() {
+                    for value_b_i in leaf_b_vs.iter().cloned() {
+                        calc_fn(
+                            &self.vs[value_a_i].0.data,
+                            self.calc_value_interaction(value_b_i),
+                            &mut write_to[value_a_i],
+                        );
+                        calc_fn(
+                            &self.vs[value_b_i].0.data,
+                            self.calc_value_interaction(value_a_i),
+                            &mut write_to[value_b_i],
+                        );
+                    }
+                }
+            },
+        );
+
+        self.push_down_node_results(&internal_accs, &leaf_accs, merge_fn, zero, write_to);
+    }
+
+    /// # Calculate the symmetric interactions on all the values with the dual-tree walk
+    ///
+    /// The same as [BarnesHutTree::calc_interactions_dual_tree], except that a near pair of values is calculated only once by [SymmetricKernel::exact_pair], writing to both values.
+    pub(crate) fn calc_symmetric_interactions_dual_tree<T: Clone>(
+        &self,
+        is_well_separated: impl Fn(&[F; D], F, &[F; D], F) -> bool,
+        kernel: &impl SymmetricKernel<D, F, T>,
+        merge_fn: impl Fn(&mut T, &T),
+        zero: T,
+        write_to: &mut [T],
+    ) {
+        let mut internal_accs: Vec<T> = vec![zero.clone(); self.internal_vec.len()];
+        let mut leaf_accs: Vec<T> = vec![zero.clone(); self.leaf_vec.len()];
+
+        self.walk_node_pairs(
+            |node_a_ref, node_b_ref| {
+                is_well_separated(
+                    self.get_node_interaction(node_a_ref).get_vc(),
+                    self.get_node_br(node_a_ref),
+                    self.get_node_interaction(node_b_ref).get_vc(),
+                    self.get_node_br(node_b_ref),
+                )
+            },
+            |node_a_ref, node_b_ref| {
+                let (a, b) = (
+                    self.get_node_interaction(node_a_ref),
+                    self.get_node_interaction(node_b_ref),
+                );
+                for (to_ref, to, from) in [(node_a_ref, &a, &b), (node_b_ref, &b, &a)] {
+                    let acc = match to_ref {
+                        NodeIndex::In(internal_i) => &mut internal_accs[*internal_i],
+                        NodeIndex::Le(leaf_i) => &mut leaf_accs[*leaf_i],
+                    };
+                    from.calc_with_kernel(to.get_vc(), kernel, acc);
+                }
+            },
+            |leaf_a_i, leaf_b_i| {
+                let leaf_a_vs = &self.leaf_vec[leaf_a_i].vs;
+                let leaf_b_vs = &self.leaf_vec[leaf_b_i].vs;
+                for (a_i, value_a_i) in leaf_a_vs.iter().cloned().enumerate() {
+                    // In the same leaf, only the values after the current one are paired.
+                    let others = if leaf_a_i == leaf_b_i {
+                        &leaf_b_vs[a_i + 1..]
+                    } else {
+                        &leaf_b_vs[..]
+                    };
+                    for value_b_i in others.iter().cloned() {
+                        let (write_to_a, write_to_b) =
+                            get_two_mut_refs(write_to, value_a_i, value_b_i);
+                        kernel.exact_pair(
+                            value_a_i,
+                            &self.vs[value_a_i].0.data,
+                            value_b_i,
+                            &self.vs[value_b_i].0.data,
+                            write_to_a,
+                            write_to_b,
+                        );
+                    }
+                }
+            },
+        );
+
+        self.push_down_node_results(&internal_accs, &leaf_accs, merge_fn, zero, write_to);
+    }
+
+    /// # Sum over the pairs with the dual-tree walk
+    ///
+    /// Each unordered pair of values is covered once by the walk. A well-separated pair of nodes adds `far_fn` of the two nodes, and a near pair of values adds `near_fn` of the two indices.
+    pub(crate) fn calc_total_over_pairs_dual_tree<'o>(
+        &'o self,
+        is_well_separated: impl Fn(&[F; D], F, &[F; D], F) -> bool,
+        far_fn: impl Fn(Interaction<'o, D, F>, Interaction<'o, D, F>) -> F,
+        near_fn: impl Fn(usize, usize) -> F,
+    ) -> F {
+        let mut far_total = F::ZERO;
+        let mut near_total = F::ZERO;
+
+        self.walk_node_pairs(
+            |node_a_ref, node_b_ref| {
+                is_well_separated(
+                    self.get_node_interaction(node_a_ref).get_vc(),
+                    self.get_node_br(node_a_ref),
+                    self.get_node_interaction(node_b_ref).get_vc(),
+                    self.get_node_br(node_b_ref),
+                )
+            },
+            |node_a_ref, node_b_ref| {
+                far_total += far_fn(
+                    self.get_node_interaction(node_a_ref),
+                    self.get_node_interaction(node_b_ref),
+                );
+            },
+            |leaf_a_i, leaf_b_i| {
+                let leaf_a_vs = &self.leaf_vec[leaf_a_i].vs;
+                let leaf_b_vs = &self.leaf_vec[leaf_b_i].vs;
+                for (a_i, value_a_i) in leaf_a_vs.iter().cloned().enumerate() {
+                    // In the same leaf, only the values after the current one are paired.
+                    let others = if leaf_a_i == leaf_b_i {
+                        &leaf_b_vs[a_i + 1..]
+                    } else {
+                        &leaf_b_vs[..]
+                    };
+                    for value_b_i in others.iter().cloned() {
+                        near_total += near_fn(value_a_i, value_b_i);
+                    }
+                }
+            },
+        );
+        far_total + near_total
+    }
+
+    /// # Push the nodes' results down to the values
+    ///
+    /// Every value merges the results of its leaf and all the internal nodes above it, summed from the root down.
+    fn push_down_node_results<T: Clone>(
+        &self,
+        internal_accs: &[T],
+        leaf_accs: &[T],
+        merge_fn: impl Fn(&mut T, &T),
+        zero: T,
+        write_to: &mut [T],
+    ) {
+        let mut stack: Vec<(&NodeIndex, T)> = Vec::new();
+        if let Some(root_ref) = self.root.as_ref() {
+            stack.push((root_ref, zero));
+        }
+        while let Some((node_box_ref, mut acc)) = stack.pop() {
+            match node_box_ref {
+                NodeIndex::In(internal_i) => {
+                    merge_fn(&mut acc, &internal_accs[*internal_i]);
+                    for next in self.internal_vec[*internal_i].nexts.iter().flatten() {
+                        stack.push((next, acc.clone()));
+                    }
+                }
+                NodeIndex::Le(leaf_i) => {
+                    merge_fn(&mut acc, &leaf_accs[*leaf_i]);
+                    for value_i in self.leaf_vec[*leaf_i].vs.iter() {
+                        merge_fn(&mut write_to[*value_i], &acc);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Get the mutable references of two different slots at once.
+#[inline]
+fn get_two_mut_refs<T>(arr: &mut [T], i: usize, j: usize) -> (&mut T, &mut T) {
+    if i < j {
+        let (left, right) = arr.split_at_mut(j);
+        (&mut left[i], &mut right[0])
+    } else {
+        let (left, right) = arr.split_at_mut(i);
+        (&mut right[0], &mut left[j])
+    }
+}
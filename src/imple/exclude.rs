@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use crate::{nodes::NodeIndex, BarnesHutTree, Fnum, Kernel, OpeningCriterion, Udim};
+
+use super::calc::Interaction;
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    /// # Calculate the interactions on one value, excluding some values
+    ///
+    /// The traversal is the same as `calc_interactions_on_value`. An excluded value is skipped when it is calculated directly. When a super node containing excluded values is accepted, the exact contributions of the excluded values are subtracted from the approximation.
+    ///
+    /// Each node containing excluded values is marked once before the traversal, so an accepted node is looked up in constant time.
+    ///
+    /// The excluded indices should be in range. Repeated indices are only excluded once.
+    pub(crate) fn calc_interactions_on_value_excluding(
+        &self,
+        value_i: usize,
+        excluded: &[usize],
+        is_super_node: &impl OpeningCriterion<D, F>,
+        kernel: &impl Kernel<D, F, [F; D]>,
+        write_to: &mut [F; D],
+    ) {
+        let mut excluded_is = excluded.to_vec();
+        excluded_is.sort_unstable();
+        excluded_is.dedup();
+
+        let mut excluded_in_nodes: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+        for excluded_i in excluded_is.iter().cloned() {
+            if excluded_i == value_i {
+                continue;
+            }
+            for node in self.get_nodes_containing_value(excluded_i) {
+                excluded_in_nodes.entry(node).or_default().push(excluded_i);
+            }
+        }
+
+        self.calc_interactions_on_value(
+            value_i,
+            is_super_node,
+            |curr_v_ref, other, write_to| {
+                if let Interaction::Value { i, .. } = other {
+                    if excluded_is.binary_search(&i).is_ok() {
+                        return;
+                    }
+                }
+                other.calc_with_kernel(curr_v_ref, kernel, write_to);
+
+                let Some(excluded_inside) = self
+                    .get_interaction_node(&other)
+                    .and_then(|node| excluded_in_nodes.get(&node))
+                else {
+                    return;
+                };
+                for excluded_i in excluded_inside.iter().cloned() {
+                    let mut contribution = [F::ZERO; D];
+                    kernel.exact(
+                        curr_v_ref,
+                        excluded_i,
+                        &self.vs[excluded_i].0.data,
+                        &mut contribution,
+                    );
+                    for d in 0..D {
+                        write_to[d] -= contribution[d];
+                    }
+                }
+            },
+            write_to,
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use std::{collections::VecDeque, thread};
+
+use crate::{nodes::NodeIndex, BarnesHutTree, Fnum, OpeningCriterion, Udim};
+
+use super::calc::Interaction;
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    /// # Calculate the interactions on all the values with multiple threads
+    ///
+    /// The calculation on each value only reads the tree, so the `write_to` slice is split into continuous chunks, one for each worker thread. Each thread calculates the values in its chunk in the order of their indices with its own queue.
+    ///
+    /// The calculation on a value is exactly the same as the sequential one, so the results are identical no matter how many threads there are.
+    pub(crate) fn calc_interactions_on_all_values_in_parallel<'o, T: Send>(
+        &'o self,
+        is_super_node: &(impl OpeningCriterion<D, F> + Sync),
+        calc_fn: impl Fn(&[F; D], Interaction<'o, D, F>, &mut T) + Sync,
+        write_to: &mut [T],
+    ) {
+        let len = write_to.len();
+        if len == 0 {
+            return;
+        }
+        let threads_num = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let chunk_len = len.div_ceil(threads_num);
+        let q_capacity = self.get_total_nodes_num() / 2;
+
+        let calc_fn = &calc_fn;
+        thread::scope(|s| {
+            for (chunk_i, chunk) in write_to.chunks_mut(chunk_len).enumerate() {
+                s.spawn(move || {
+                    let mut q: VecDeque<&NodeIndex> = VecDeque::with_capacity(q_capacity);
+                    let start = chunk_i * chunk_len;
+                    for (i, curr_write_to) in chunk.iter_mut().enumerate() {
+                        self.calc_interactions_on_value_with_queue(
+                            start + i,
+                            is_super_node,
+                            calc_fn,
+                            curr_write_to,
+                            &mut q,
+                        );
+                    }
+                });
+            }
+        });
+    }
+}
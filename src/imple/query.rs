@@ -0,0 +1,310 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+use crate::{boundbox::BoundBox, nodes::NodeIndex, BarnesHutTree, Fnum, Udim};
+
+use super::get_ref_from_arr_ref;
+
+/// # An item ordered by its squared distance, and then by its index
+struct ByDisPow2<F: Fnum, T> {
+    dis_pow2: F,
+    i: usize,
+    item: T,
+}
+
+impl<F: Fnum, T> PartialEq for ByDisPow2<F, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<F: Fnum, T> Eq for ByDisPow2<F, T> {}
+
+impl<F: Fnum, T> PartialOrd for ByDisPow2<F, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: Fnum, T> Ord for ByDisPow2<F, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.dis_pow2
+            .partial_cmp(&other.dis_pow2)
+            .unwrap_or(Ordering::Equal)
+            .then(self.i.cmp(&other.i))
+    }
+}
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    #[inline]
+    pub(crate) fn get_node_bb(&self, node_box_ref: &NodeIndex) -> &BoundBox<D, F> {
+        match node_box_ref {
+            NodeIndex::In(internal_i) => {
+                &get_ref_from_arr_ref(
+                    &self.internal_vec,
+                    *internal_i,
+                    "Getting the internal bounding box",
+                )
+                .bb
+            }
+            NodeIndex::Le(leaf_i) => {
+                &get_ref_from_arr_ref(&self.leaf_vec, *leaf_i, "Getting the leaf bounding box").bb
+            }
+        }
+    }
+
+    #[inline]
+    fn calc_value_dis_pow2(&self, point: &[F; D], value_i: usize) -> F {
+        let v = &self.vs[value_i].0.data;
+        let mut ans = F::ZERO;
+        for d in 0..D {
+            let diff = v[d] - point[d];
+            ans += diff * diff;
+        }
+        ans
+    }
+
+    /// # Find the k nearest values to a point
+    ///
+    /// The nodes are visited best-first, by the distance from the point to their bounding hypercubes. Once the k-th nearest value found so far is closer than the nearest unvisited node, the remaining nodes are pruned.
+    ///
+    /// The value `skip_value_i` is never counted, so that a value is not the neighbour of itself. Ties in distance are broken by the smaller index.
+    pub(crate) fn find_knn(
+        &self,
+        point: &[F; D],
+        k: usize,
+        skip_value_i: Option<usize>,
+    ) -> Vec<(usize, F)> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        // The farthest of the best values found so far is on the top.
+        let mut best: BinaryHeap<ByDisPow2<F, ()>> =
+            BinaryHeap::with_capacity(k.min(self.vs.len()) + 1);
+        // The nearest unvisited node is on the top.
+        let mut nodes: BinaryHeap<Reverse<ByDisPow2<F, &NodeIndex>>> = BinaryHeap::new();
+        if let Some(root_ref) = self.root.as_ref() {
+            nodes.push(Reverse(ByDisPow2 {
+                dis_pow2: self.get_node_bb(root_ref).calc_min_dis_pow2(point),
+                i: 0,
+                item: root_ref,
+            }));
+        }
+
+        while let Some(Reverse(curr)) = nodes.pop() {
+            if best.len() == k
+                && best
+                    .peek()
+                    .is_some_and(|farthest| farthest.dis_pow2 < curr.dis_pow2)
+            {
+                break;
+            }
+            match curr.item {
+                NodeIndex::In(internal_i) => {
+                    let internal_ref = get_ref_from_arr_ref(
+                        &self.internal_vec,
+                        *internal_i,
+                        "Getting the internal node to search its children",
+                    );
+                    for (dir_i, node_box_ref) in internal_ref.nexts.iter().enumerate() {
+                        if let Some(node_box_ref) = node_box_ref {
+                            nodes.push(Reverse(ByDisPow2 {
+                                dis_pow2: self.get_node_bb(node_box_ref).calc_min_dis_pow2(point),
+                                i: dir_i,
+                                item: node_box_ref,
+                            }));
+                        }
+                    }
+                }
+                NodeIndex::Le(leaf_i) => {
+                    let leaf_ref = get_ref_from_arr_ref(
+                        &self.leaf_vec,
+                        *leaf_i,
+                        "Getting the leaf node to search its values",
+                    );
+                    for value_i in leaf_ref.vs.iter().cloned() {
+                        if Some(value_i) == skip_value_i {
+                            continue;
+                        }
+                        best.push(ByDisPow2 {
+                            dis_pow2: self.calc_value_dis_pow2(point, value_i),
+                            i: value_i,
+                            item: (),
+                        });
+                        if best.len() > k {
+                            best.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|curr| (curr.i, curr.dis_pow2.sqrt()))
+            .collect()
+    }
+
+    /// # Visit the values within a distance to a point
+    ///
+    /// A node is skipped with all its children if its bounding hypercube does not intersect the ball. Otherwise, every value in a reached leaf is checked, including the values sharing one leaf below the radius limit.
+    ///
+    /// The value `skip_value_i` is never visited. Nothing is visited if the radius is negative.
+    pub(crate) fn visit_ball(
+        &self,
+        point: &[F; D],
+        r: F,
+        skip_value_i: Option<usize>,
+        visit_fn: &mut impl FnMut(usize),
+    ) {
+        if r < F::ZERO {
+            return;
+        }
+        let r_pow2 = r * r;
+
+        let mut stack: Vec<&NodeIndex> = Vec::new();
+        if let Some(root_ref) = self.root.as_ref() {
+            stack.push(root_ref);
+        }
+        while let Some(node_box_ref) = stack.pop() {
+            if self.get_node_bb(node_box_ref).calc_min_dis_pow2(point) > r_pow2 {
+                continue;
+            }
+            match node_box_ref {
+                NodeIndex::In(internal_i) => {
+                    let internal_ref = get_ref_from_arr_ref(
+                        &self.internal_vec,
+                        *internal_i,
+                        "Getting the internal node to search its children",
+                    );
+                    for next in internal_ref.nexts.iter().rev().flatten() {
+                        stack.push(next);
+                    }
+                }
+                NodeIndex::Le(leaf_i) => {
+                    let leaf_ref = get_ref_from_arr_ref(
+                        &self.leaf_vec,
+                        *leaf_i,
+                        "Getting the leaf node to search its values",
+                    );
+                    for value_i in leaf_ref.vs.iter().cloned() {
+                        if Some(value_i) != skip_value_i
+                            && self.calc_value_dis_pow2(point, value_i) <= r_pow2
+                        {
+                            visit_fn(value_i);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// # Visit the values inside an axis-aligned box
+    ///
+    /// A node is skipped with all its children if its bounding hypercube is disjoint from the box. Once a node is inside the box, all the values under it are visited without checking them one by one.
+    pub(crate) fn visit_aabb(&self, min: &[F; D], max: &[F; D], visit_fn: &mut impl FnMut(usize)) {
+        // The flag tells whether the node is known to be inside the box.
+        let mut stack: Vec<(&NodeIndex, bool)> = Vec::new();
+        if let Some(root_ref) = self.root.as_ref() {
+            stack.push((root_ref, false));
+        }
+        while let Some((node_box_ref, is_inside)) = stack.pop() {
+            let is_inside = if is_inside {
+                true
+            } else {
+                let bb = self.get_node_bb(node_box_ref);
+                if bb.is_disjoint_from_aabb(min, max) {
+                    continue;
+                }
+                bb.is_inside_aabb(min, max)
+            };
+            match node_box_ref {
+                NodeIndex::In(internal_i) => {
+                    let internal_ref = get_ref_from_arr_ref(
+                        &self.internal_vec,
+                        *internal_i,
+                        "Getting the internal node to search its children",
+                    );
+                    for next in internal_ref.nexts.iter().rev().flatten() {
+                        stack.push((next, is_inside));
+                    }
+                }
+                NodeIndex::Le(leaf_i) => {
+                    let leaf_ref = get_ref_from_arr_ref(
+                        &self.leaf_vec,
+                        *leaf_i,
+                        "Getting the leaf node to search its values",
+                    );
+                    for value_i in leaf_ref.vs.iter().cloned() {
+                        let v = &self.vs[value_i].0.data;
+                        if is_inside || (0..D).all(|d| v[d] >= min[d] && v[d] <= max[d]) {
+                            visit_fn(value_i);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// # Visit the pairs of values within a distance
+    ///
+    /// The pairs of nodes are walked with `walk_node_pairs`, and a pair of nodes is accepted as "well-separated" when their bounding hypercubes are at least the distance away, so it holds no pair and is skipped. For a near pair of leaves, every pair of values is checked, including the pairs inside one leaf below the radius limit.
+    ///
+    /// Each pair is visited once with the smaller index first. Nothing is visited if the distance is not positive.
+    pub(crate) fn visit_pairs_within(&self, r: F, visit_fn: &mut impl FnMut(usize, usize, F)) {
+        if r <= F::ZERO {
+            return;
+        }
+        let r_pow2 = r * r;
+
+        let mut check_pair = |value_a_i: usize, value_b_i: usize| {
+            let dis_pow2 = self.calc_value_dis_pow2(&self.vs[value_b_i].0.data, value_a_i);
+            if dis_pow2 < r_pow2 {
+                if value_a_i < value_b_i {
+                    visit_fn(value_a_i, value_b_i, dis_pow2.sqrt());
+                } else {
+                    visit_fn(value_b_i, value_a_i, dis_pow2.sqrt());
+                }
+            }
+        };
+
+        self.walk_node_pairs(
+            |node_a_ref, node_b_ref| {
+                self.get_node_bb(node_a_ref)
+                    .calc_min_dis_pow2_to_bb(self.get_node_bb(node_b_ref))
+                    >= r_pow2
+            },
+            |_, _| {},
+            |leaf_a_i, leaf_b_i| {
+                let leaf_a_vs = &get_ref_from_arr_ref(
+                    &self.leaf_vec,
+                    leaf_a_i,
+                    "Getting the first leaf of a near pair",
+                )
+                .vs;
+                if leaf_a_i == leaf_b_i {
+                    for (in_leaf_i, value_a_i) in leaf_a_vs.iter().enumerate() {
+                        for value_b_i in leaf_a_vs[in_leaf_i + 1..].iter() {
+                            check_pair(*value_a_i, *value_b_i);
+                        }
+                    }
+                } else {
+                    let leaf_b_vs = &get_ref_from_arr_ref(
+                        &self.leaf_vec,
+                        leaf_b_i,
+                        "Getting the second leaf of a near pair",
+                    )
+                    .vs;
+                    for value_a_i in leaf_a_vs.iter() {
+                        for value_b_i in leaf_b_vs.iter() {
+                            check_pair(*value_a_i, *value_b_i);
+                        }
+                    }
+                }
+            },
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{BarnesHutTree, Udim};
+use crate::{BarnesHutTree, Fnum, Udim};
 
 use super::{get_mut_ref_from_arr_mut_ref, get_ref_from_arr_ref};
 
@@ -6,7 +6,9 @@ mod remove_from_direct;
 
 mod drop_one_child_nodes;
 
-impl<const D: Udim> BarnesHutTree<D> {
+mod shrink_root;
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     fn sub_value_util_root(&mut self, internal_i: usize, value_i: usize) {
         let mut curr_internal_mut_ref_opt = Some(get_mut_ref_from_arr_mut_ref(
             &mut self.internal_vec,
@@ -15,9 +17,11 @@ impl<const D: Udim> BarnesHutTree<D> {
         ));
 
         let to_sub_v_ref = &get_ref_from_arr_ref(&self.vs, value_i, "Getting the to-sub value").0;
+        let to_sub_w = self.ws[value_i];
+        let to_sub_q = self.qs[value_i];
 
         while let Some(curr_internal_mut_ref) = curr_internal_mut_ref_opt {
-            curr_internal_mut_ref.sub_value(to_sub_v_ref);
+            curr_internal_mut_ref.sub_value(to_sub_v_ref, to_sub_w, to_sub_q);
 
             if let Some((parent_i, _)) = curr_internal_mut_ref.parent {
                 curr_internal_mut_ref_opt = Some(get_mut_ref_from_arr_mut_ref(
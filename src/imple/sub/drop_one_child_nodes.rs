@@ -1,11 +1,11 @@
 use crate::{
     imple::get_mut_ref_from_arr_mut_ref,
-    BarnesHutTree,
+    BarnesHutTree, Fnum,
     NodeIndex::{In, Le},
     Udim,
 };
 
-impl<const D: Udim> BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     /// # Drop One-Child Internals
     ///
     /// After we have cut the to-remove value from the leaf and the leaf from its parent internal node, the parent internal node might only holds one child, and we need to cut these nodes off until an internal node with more than one leaves.
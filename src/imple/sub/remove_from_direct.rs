@@ -1,9 +1,9 @@
 use crate::{
     imple::{get_mut_ref_from_arr_mut_ref, get_ref_from_arr_ref},
-    BarnesHutTree, Udim,
+    BarnesHutTree, Fnum, Udim,
 };
 
-impl<const D: Udim> BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     /// # Removing the leaf value from the direct leaf node
     ///
     /// An added leaf value always have a direct leaf node parent containing that value.
@@ -43,7 +43,12 @@ impl<const D: Udim> BarnesHutTree<D> {
         );
 
         if parent_leaf_mut_ref.get_values_num_inside() > 1 {
-            let replaced_leaf_i = parent_leaf_mut_ref.sub_value(idx, &value_info.0);
+            let replaced_leaf_i = parent_leaf_mut_ref.sub_value(
+                idx,
+                &value_info.0,
+                self.ws[value_i],
+                self.qs[value_i],
+            );
 
             if replaced_leaf_i > 0 {
                 #[cfg(feature = "unchecked")]
@@ -0,0 +1,82 @@
+use crate::{
+    imple::{get_mut_ref_from_arr_mut_ref, get_ref_from_arr_ref},
+    BarnesHutTree, Fnum,
+    NodeIndex::{In, Le},
+    Udim,
+};
+
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    /// # Drop one-child root internals
+    ///
+    /// The root only expands when adding values, and removing values only cuts the one-child internals under a more-than-2-value-holding internal node. So, after the values have moved inward, the root might be an internal node with only one child, and so on for a chain of internal nodes.
+    ///
+    /// ## Dropping the root
+    ///
+    /// While the root internal node has only one child, we drop the root and let its child become the new root.
+    ///
+    /// ## Tightening the bounding box
+    ///
+    /// The struct's bounding box follows the new root's bounding box. If the new root is a leaf, we stop since there is nothing under it to shrink.
+    pub(crate) fn drop_one_child_roots(&mut self) -> bool {
+        let mut is_shrunk = false;
+        while let Some(In(root_i)) = self.root {
+            let root_ref =
+                get_ref_from_arr_ref(&self.internal_vec, root_i, "Getting the root to shrink");
+
+            let mut only_child_opt = None;
+            for next in root_ref.nexts.iter().flatten() {
+                if only_child_opt.is_some() {
+                    return is_shrunk;
+                }
+                only_child_opt = Some(match next {
+                    In(internal_i) => In(*internal_i),
+                    Le(leaf_i) => Le(*leaf_i),
+                });
+            }
+
+            match only_child_opt {
+                Some(In(mut child_i)) => {
+                    get_mut_ref_from_arr_mut_ref(
+                        &mut self.internal_vec,
+                        child_i,
+                        "Cutting the child from the root",
+                    )
+                    .parent = None;
+
+                    // The child's index changes if it is the last one replacing the dropped root.
+                    if let Some((old_i, new_i)) = self.drop_internal(root_i) {
+                        if old_i == child_i {
+                            child_i = new_i;
+                        }
+                    }
+
+                    let child_ref = get_ref_from_arr_ref(
+                        &self.internal_vec,
+                        child_i,
+                        "Getting the new root internal to update struct bounding box",
+                    );
+                    self.bb.clone_from(&child_ref.bb);
+                    self.root = Some(In(child_i));
+                }
+                Some(Le(leaf_i)) => {
+                    self.drop_internal(root_i);
+
+                    let leaf_mut_ref = get_mut_ref_from_arr_mut_ref(
+                        &mut self.leaf_vec,
+                        leaf_i,
+                        "Getting the new root leaf to update struct bounding box",
+                    );
+                    leaf_mut_ref.parent = None;
+                    self.bb.clone_from(&leaf_mut_ref.bb);
+                    self.root = Some(Le(leaf_i));
+                }
+                None => {
+                    debug_assert!(false, "A root internal having none children...");
+                    return is_shrunk;
+                }
+            }
+            is_shrunk = true;
+        }
+        is_shrunk
+    }
+}
@@ -296,3 +296,380 @@ fn check_sub_from_tree_with_adding_to_same_leaf() -> Result<(), Box<dyn std::err
     assert_bht_serde_eq(&bht_ser, &expected_bht_ser);
     Ok(())
 }
+
+#[test]
+fn check_weighted_value_centers() -> Result<(), Box<dyn std::error::Error>> {
+    let mut bht: BarnesHutTree<2> = BarnesHutTree::with_bounding_and_capacity(&[0.0, 0.0], 4.0, 2);
+    bht.push_weighted(&[1.0, 3.0], 1.0);
+    bht.push_weighted(&[3.0, 1.0], 3.0);
+
+    let expected_bht_ser: BarnesHutTreeSer<2> = serde_json::from_str(
+        "{
+            \"dim\":2,
+            \"num\":4,
+            \"vcs\":[2.5,1.5,2.5,1.5,1.0,3.0,3.0,1.0],
+            \"bcs\":[0.0,0.0,2.0,2.0,1.0,3.0,3.0,1.0],
+            \"brs\":[4.0,2.0,1.0,1.0],
+            \"ns\":[2,2,1,1],
+
+            \"parents\":[null,0,1,1],
+            \"from_dirs\":[null,3,1,2],
+            \"vs\":[1.0,3.0,3.0,1.0],
+            \"to_leafs\":[2,3],
+            \"idxs\":[0,0]
+        }",
+    )?;
+
+    let bht_ser = bht.calc_serialized();
+    assert_bht_serde_eq(&bht_ser, &expected_bht_ser);
+
+    bht.update_weight(1, 1.0);
+
+    let expected_bht_ser: BarnesHutTreeSer<2> = serde_json::from_str(
+        "{
+            \"dim\":2,
+            \"num\":4,
+            \"vcs\":[2.0,2.0,2.0,2.0,1.0,3.0,3.0,1.0],
+            \"bcs\":[0.0,0.0,2.0,2.0,1.0,3.0,3.0,1.0],
+            \"brs\":[4.0,2.0,1.0,1.0],
+            \"ns\":[2,2,1,1],
+
+            \"parents\":[null,0,1,1],
+            \"from_dirs\":[null,3,1,2],
+            \"vs\":[1.0,3.0,3.0,1.0],
+            \"to_leafs\":[2,3],
+            \"idxs\":[0,0]
+        }",
+    )?;
+
+    let bht_ser = bht.calc_serialized();
+    assert_bht_serde_eq(&bht_ser, &expected_bht_ser);
+
+    bht.update_weight(0, 3.0);
+    bht.sub(1);
+
+    let expected_bht_ser: BarnesHutTreeSer<2> = serde_json::from_str(
+        "{
+            \"dim\":2,
+            \"num\":1,
+            \"vcs\":[1.0,3.0],
+            \"bcs\":[0.0,0.0],
+            \"brs\":[4.0],
+            \"ns\":[1],
+
+            \"parents\":[null],
+            \"from_dirs\":[null],
+            \"vs\":[1.0,3.0,3.0,1.0],
+            \"to_leafs\":[0,null],
+            \"idxs\":[0,null]
+        }",
+    )?;
+
+    let bht_ser = bht.calc_serialized();
+    assert_bht_serde_eq(&bht_ser, &expected_bht_ser);
+    Ok(())
+}
+
+#[cfg(feature = "quadrupole")]
+#[test]
+fn check_root_moment_after_updates_and_removal() {
+    use crate::nodes::NodeIndex;
+
+    let vals: Vec<[f64; 2]> = vec![[1.0, 3.0], [3.0, 1.0], [-2.0, 0.5], [0.5, -3.5], [2.5, 2.5]];
+    let mut bht: BarnesHutTree<2> =
+        BarnesHutTree::with_bounding_and_values(&[0.0, 0.0], 4.0, &vals);
+
+    bht.update(1, &[7.0, -1.0]);
+    bht.update_weight(2, 3.0);
+    bht.remove(0);
+
+    let mut total_w = 0.0;
+    let mut vc = [0.0; 2];
+    for value_i in 0..bht.vs.len() {
+        let w = bht.ws[value_i];
+        total_w += w;
+        for (vc_d, v_d) in vc.iter_mut().zip(bht.vs[value_i].0.data.iter()) {
+            *vc_d += w * v_d;
+        }
+    }
+    for vc_d in vc.iter_mut() {
+        *vc_d /= total_w;
+    }
+    let mut expected_moment = [[0.0; 2]; 2];
+    for value_i in 0..bht.vs.len() {
+        let w = bht.ws[value_i];
+        let v = &bht.vs[value_i].0.data;
+        for i in 0..2 {
+            for j in 0..2 {
+                expected_moment[i][j] += w * (v[i] - vc[i]) * (v[j] - vc[j]);
+            }
+        }
+    }
+
+    let root_moment = match bht.root {
+        Some(NodeIndex::In(root_i)) => bht.internal_vec[root_i].moment.data,
+        _ => unreachable!("The root should be an internal node"),
+    };
+    for i in 0..2 {
+        for j in 0..2 {
+            assert!(
+                (root_moment[i][j] - expected_moment[i][j]).abs() < 1e-9,
+                "     Got:{:?}\nExpected:{:?}",
+                root_moment,
+                expected_moment
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+fn assert_same_aggregates_on_paths<const D: Udim>(
+    bht: &BarnesHutTree<D>,
+    expected: &BarnesHutTree<D>,
+) {
+    for value_i in 0..bht.vs.len() {
+        let (leaf_i, _) = bht.vs[value_i].1.expect("The value should be in the tree");
+        let (expected_leaf_i, _) = expected.vs[value_i]
+            .1
+            .expect("The value should be in the tree");
+        let (leaf_ref, expected_leaf_ref) =
+            (&bht.leaf_vec[leaf_i], &expected.leaf_vec[expected_leaf_i]);
+        assert_eq!(leaf_ref.weight, expected_leaf_ref.weight);
+        assert_eq!(leaf_ref.charges, expected_leaf_ref.charges);
+
+        let (mut parent, mut expected_parent) = (leaf_ref.parent, expected_leaf_ref.parent);
+        while let (Some((parent_i, _)), Some((expected_parent_i, _))) = (parent, expected_parent) {
+            let (internal_ref, expected_internal_ref) = (
+                &bht.internal_vec[parent_i],
+                &expected.internal_vec[expected_parent_i],
+            );
+            assert_eq!(internal_ref.weight, expected_internal_ref.weight);
+            assert_eq!(internal_ref.charges, expected_internal_ref.charges);
+            parent = internal_ref.parent;
+            expected_parent = expected_internal_ref.parent;
+        }
+        assert!(parent.is_none() && expected_parent.is_none());
+    }
+}
+
+#[cfg(test)]
+fn check_build_same_as_adding_one_by_one<const D: Udim>(len: usize, br_limit: f64) {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let mut vals: Vec<[f64; D]> = Vec::with_capacity(len);
+    for i in 0..len {
+        if i % 10 == 9 {
+            // Some identical values to fill the leaves at the limit.
+            vals.push(vals[i / 2]);
+            continue;
+        }
+        vals.push(std::array::from_fn(|_| rng.gen_range(-10.0..10.0)));
+    }
+    let ws: Vec<f64> = (0..len).map(|_| rng.gen_range(0.5..2.0)).collect();
+    let qs: Vec<f64> = (0..len).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+    let mut bht: BarnesHutTree<D> =
+        BarnesHutTree::new_without_add(&[0.0; D], 16.0, &vals, br_limit);
+    bht.ws.clone_from(&ws);
+    bht.qs.clone_from(&qs);
+    bht.build();
+
+    let mut expected_bht: BarnesHutTree<D> =
+        BarnesHutTree::new_without_add(&[0.0; D], 16.0, &vals, br_limit);
+    expected_bht.ws.clone_from(&ws);
+    expected_bht.qs.clone_from(&qs);
+    for i in 0..len {
+        expected_bht.add(i);
+    }
+
+    assert_bht_serde_eq(&bht.calc_serialized(), &expected_bht.calc_serialized());
+    assert_same_aggregates_on_paths(&bht, &expected_bht);
+    assert_eq!(
+        bht.get_total_nodes_num(),
+        expected_bht.get_total_nodes_num()
+    );
+}
+
+#[test]
+fn check_build_same_as_adding_one_by_one_dim_2() {
+    check_build_same_as_adding_one_by_one::<2>(1000, 1e-8);
+    check_build_same_as_adding_one_by_one::<2>(1000, 0.5);
+    check_build_same_as_adding_one_by_one::<2>(1, 1e-8);
+}
+
+#[test]
+fn check_build_same_as_adding_one_by_one_dim_3() {
+    check_build_same_as_adding_one_by_one::<3>(1000, 1e-8);
+    check_build_same_as_adding_one_by_one::<3>(1000, 0.5);
+}
+
+#[test]
+fn check_with_bounding_and_values_not_containing_all() -> Result<(), Box<dyn std::error::Error>> {
+    let vals: Vec<[f64; 2]> = vec![[1.0, 3.0], [3.0, 1.0], [-9.0, 5.0], [30.0, -2.0]];
+
+    let bht: BarnesHutTree<2> = BarnesHutTree::with_bounding_and_values(&[0.0, 0.0], 4.0, &vals);
+
+    let mut expected_bht: BarnesHutTree<2> =
+        BarnesHutTree::with_bounding_and_capacity(&[0.0, 0.0], 4.0, vals.len());
+    for v in vals.iter() {
+        expected_bht.push(v);
+    }
+
+    assert_bht_serde_eq(&bht.calc_serialized(), &expected_bht.calc_serialized());
+    Ok(())
+}
+
+#[cfg(test)]
+fn check_out_of_bounds_build_same_as_pushing_one_by_one<const D: Udim>(len: usize, br_limit: f64) {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let mut vals: Vec<[f64; D]> = Vec::with_capacity(len);
+    for i in 0..len {
+        if i % 10 == 9 {
+            vals.push(vals[i / 2]);
+            continue;
+        }
+        vals.push(std::array::from_fn(|_| rng.gen_range(-100.0..100.0)));
+    }
+
+    let bht: BarnesHutTree<D> =
+        BarnesHutTree::with_bounding_and_values_and_limit(&[0.5; D], 1.0, &vals, br_limit);
+
+    let mut expected_bht: BarnesHutTree<D> =
+        BarnesHutTree::with_bounding_and_capacity_and_limit(&[0.5; D], 1.0, len, br_limit);
+    for v in vals.iter() {
+        expected_bht.push(v);
+    }
+
+    assert_bht_serde_eq(&bht.calc_serialized(), &expected_bht.calc_serialized());
+    assert_same_aggregates_on_paths(&bht, &expected_bht);
+}
+
+#[test]
+fn check_out_of_bounds_build_same_as_pushing_one_by_one_dim_2() {
+    check_out_of_bounds_build_same_as_pushing_one_by_one::<2>(1000, 1e-8);
+    check_out_of_bounds_build_same_as_pushing_one_by_one::<2>(1000, 0.5);
+    check_out_of_bounds_build_same_as_pushing_one_by_one::<2>(1, 1e-8);
+}
+
+#[test]
+fn check_out_of_bounds_build_same_as_pushing_one_by_one_dim_3() {
+    check_out_of_bounds_build_same_as_pushing_one_by_one::<3>(1000, 1e-8);
+    check_out_of_bounds_build_same_as_pushing_one_by_one::<3>(1000, 0.5);
+}
+
+#[test]
+fn check_push_weighted_and_charged_same_as_adding_one_by_one() {
+    let vals: Vec<[f64; 2]> = vec![
+        [1.0, 3.0],
+        [3.0, 1.0],
+        [-3.0, -3.0],
+        [-2.5, -3.5],
+        [30.0, -2.0],
+    ];
+    let ws: Vec<f64> = vec![2.0, 0.5, 1.0, 3.0, 1.5];
+    let qs: Vec<f64> = vec![-1.0, 0.5, 0.0, 2.0, -0.25];
+
+    let mut bht: BarnesHutTree<2> =
+        BarnesHutTree::with_bounding_and_capacity(&[0.0, 0.0], 4.0, vals.len());
+    for ((v, w), q) in vals.iter().zip(ws.iter()).zip(qs.iter()) {
+        bht.push_weighted_and_charged(v, *w, *q);
+    }
+
+    let mut expected_bht: BarnesHutTree<2> =
+        BarnesHutTree::new_without_add(&[0.0, 0.0], 4.0, &vals, 1e-8);
+    expected_bht.ws.clone_from(&ws);
+    expected_bht.qs.clone_from(&qs);
+    for i in 0..vals.len() {
+        expected_bht.add(i);
+    }
+
+    assert_bht_serde_eq(&bht.calc_serialized(), &expected_bht.calc_serialized());
+    assert_same_aggregates_on_paths(&bht, &expected_bht);
+    for i in 0..vals.len() {
+        assert_eq!(bht.get_weight(i), Some(ws[i]));
+        assert_eq!(bht.get_charge(i), Some(qs[i]));
+    }
+}
+
+#[test]
+fn check_shrink_root_to_internal() -> Result<(), Box<dyn std::error::Error>> {
+    let vals: Vec<[f64; 2]> = vec![[1.0, 3.0], [3.0, 1.0], [-3.0, -3.0]];
+
+    let mut bht: BarnesHutTree<2> =
+        BarnesHutTree::with_bounding_and_values(&[0.0, 0.0], 4.0, &vals);
+    let original_bht_ser = bht.calc_serialized();
+
+    bht.remove(2);
+    assert!(bht.shrink_root());
+
+    let expected_bht_ser: BarnesHutTreeSer<2> = serde_json::from_str(
+        "{
+            \"dim\":2,
+            \"num\":3,
+            \"vcs\":[2.0,2.0,1.0,3.0,3.0,1.0],
+            \"bcs\":[2.0,2.0,1.0,3.0,3.0,1.0],
+            \"brs\":[2.0,1.0,1.0],
+            \"ns\":[2,1,1],
+            \"parents\":[null,0,0],
+            \"from_dirs\":[null,1,2],
+
+            \"vs\":[1.0,3.0,3.0,1.0],
+            \"to_leafs\":[1,2],
+            \"idxs\":[0,0]
+        }",
+    )
+    .unwrap();
+    let bht_ser = bht.calc_serialized();
+    assert_bht_serde_eq(&bht_ser, &expected_bht_ser);
+    assert_eq!(bht.bb.bc.data, [2.0, 2.0]);
+    assert_eq!(bht.bb.br, 2.0);
+
+    assert!(!bht.shrink_root());
+
+    // Expanding the root again leads to the same tree.
+    bht.push(&[-3.0, -3.0]);
+    let bht_ser = bht.calc_serialized();
+    assert_bht_serde_eq(&bht_ser, &original_bht_ser);
+
+    Ok(())
+}
+
+#[test]
+fn check_shrink_root_to_leaf() -> Result<(), Box<dyn std::error::Error>> {
+    let vals: Vec<[f64; 2]> = vec![[1.0, 1.0], [1.5, 1.5], [-1.0, -1.0]];
+
+    let mut bht: BarnesHutTree<2> =
+        BarnesHutTree::with_bounding_and_values_and_limit(&[0.0, 0.0], 4.0, &vals, 2.0);
+
+    bht.remove(2);
+    assert!(bht.shrink_root());
+
+    let expected_bht_ser: BarnesHutTreeSer<2> = serde_json::from_str(
+        "{
+            \"dim\":2,
+            \"num\":1,
+            \"vcs\":[1.25,1.25],
+            \"bcs\":[2.0,2.0],
+            \"brs\":[2.0],
+            \"ns\":[2],
+            \"parents\":[null],
+            \"from_dirs\":[null],
+
+            \"vs\":[1.0,1.0,1.5,1.5],
+            \"to_leafs\":[0,0],
+            \"idxs\":[0,1]
+        }",
+    )
+    .unwrap();
+    let bht_ser = bht.calc_serialized();
+    assert_bht_serde_eq(&bht_ser, &expected_bht_ser);
+    assert_eq!(bht.get_total_nodes_num(), 1);
+
+    bht.push(&[3.5, 1.0]);
+    assert_eq!(bht.get_total_nodes_num(), 1);
+
+    Ok(())
+}
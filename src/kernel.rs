@@ -0,0 +1,119 @@
+use crate::{Fnum, NodeSummary, Udim};
+
+/// # The kernel calculating the interactions on a target
+///
+/// The kernel tells a single other value from a super node: [Kernel::exact] is called for another value with its index, and [Kernel::approx] is called for a "far" super node as a whole. So a kernel can, for example, soften only the approximated interactions, or skip the values that are neighbours of the target in a graph.
+///
+/// Any closure `Fn(&[F; D], &[F; D], usize, &mut T)` taking the position of the target, the (average) position of the other value(s), the number of values, and the answer's mutable reference is a kernel. Both methods call the closure, with the number of values being one for a single value.
+pub trait Kernel<const D: Udim, F: Fnum, T> {
+    /// Calculate the interaction on the target at `curr_v_ref` by the value `other_i` at `other_v_ref`.
+    fn exact(&self, curr_v_ref: &[F; D], other_i: usize, other_v_ref: &[F; D], write_to: &mut T);
+
+    /// Calculate the interaction on the target at `curr_v_ref` by a super node as a whole.
+    fn approx(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>, write_to: &mut T);
+}
+
+impl<const D: Udim, F: Fnum, T, C: Fn(&[F; D], &[F; D], usize, &mut T)> Kernel<D, F, T> for C {
+    #[inline]
+    fn exact(&self, curr_v_ref: &[F; D], _: usize, other_v_ref: &[F; D], write_to: &mut T) {
+        self(curr_v_ref, other_v_ref, 1, write_to)
+    }
+
+    #[inline]
+    fn approx(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>, write_to: &mut T) {
+        self(curr_v_ref, node.vc, node.count, write_to)
+    }
+}
+
+/// # The kernel passing the indices of both values to the exact calculations
+///
+/// The exact closure takes the index and the position of the target, the index and the position of the other value, and the answer's mutable reference. The approximated closure is the same as the closure form of [Kernel].
+pub(crate) struct IndexedKernel<E, A> {
+    pub(crate) target_i: usize,
+    pub(crate) exact_fn: E,
+    pub(crate) approx_fn: A,
+}
+
+impl<
+        const D: Udim,
+        F: Fnum,
+        T,
+        E: Fn(usize, &[F; D], usize, &[F; D], &mut T),
+        A: Fn(&[F; D], &[F; D], usize, &mut T),
+    > Kernel<D, F, T> for IndexedKernel<E, A>
+{
+    #[inline]
+    fn exact(&self, curr_v_ref: &[F; D], other_i: usize, other_v_ref: &[F; D], write_to: &mut T) {
+        (self.exact_fn)(self.target_i, curr_v_ref, other_i, other_v_ref, write_to)
+    }
+
+    #[inline]
+    fn approx(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>, write_to: &mut T) {
+        (self.approx_fn)(curr_v_ref, node.vc, node.count, write_to)
+    }
+}
+
+/// # The kernel of a pairwise interaction obeying Newton's third law
+///
+/// A symmetric kernel is a [Kernel] whose exact interaction on one value of a pair is the opposite of the one on the other value. [Kernel] is enough for any interaction, on one target or on all the values. A symmetric kernel only applies to [crate::BarnesHutTree::calc_symmetric_forces_dual_tree], where each near pair of values is calculated once by [SymmetricKernel::exact_pair], and the far pairs of nodes still go through [Kernel::approx] in both directions.
+///
+/// A closure `Fn(&[F; D], &[F; D]) -> [F; D]` returning the contribution on the first value by the second one becomes a symmetric kernel when wrapped in [PairKernel].
+pub trait SymmetricKernel<const D: Udim, F: Fnum, T>: Kernel<D, F, T> {
+    /// Calculate the interactions between the value `v0_i` at `v0_ref` and the value `v1_i` at `v1_ref` once, writing the one on the first value to `write_to_0`, and the opposite one on the second value to `write_to_1`.
+    fn exact_pair(
+        &self,
+        v0_i: usize,
+        v0_ref: &[F; D],
+        v1_i: usize,
+        v1_ref: &[F; D],
+        write_to_0: &mut T,
+        write_to_1: &mut T,
+    );
+}
+
+/// # The symmetric kernel of a pair closure
+///
+/// The closure takes the positions of two values and returns the contribution (a vector, like a force or a displacement) on the first value by the second one. The second value receives exactly the opposite contribution. A super node is treated as its number of values sitting at the average position.
+pub struct PairKernel<K>(pub K);
+
+impl<const D: Udim, F: Fnum, K: Fn(&[F; D], &[F; D]) -> [F; D]> Kernel<D, F, [F; D]>
+    for PairKernel<K>
+{
+    #[inline]
+    fn exact(&self, curr_v_ref: &[F; D], _: usize, other_v_ref: &[F; D], write_to: &mut [F; D]) {
+        let contribution = (self.0)(curr_v_ref, other_v_ref);
+        for (ans_d, contribution_d) in write_to.iter_mut().zip(contribution.iter()) {
+            *ans_d += *contribution_d;
+        }
+    }
+
+    #[inline]
+    fn approx(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>, write_to: &mut [F; D]) {
+        let contribution = (self.0)(curr_v_ref, node.vc);
+        let num_fnum = F::from_usize(node.count);
+        for (ans_d, contribution_d) in write_to.iter_mut().zip(contribution.iter()) {
+            *ans_d += *contribution_d * num_fnum;
+        }
+    }
+}
+
+impl<const D: Udim, F: Fnum, K: Fn(&[F; D], &[F; D]) -> [F; D]> SymmetricKernel<D, F, [F; D]>
+    for PairKernel<K>
+{
+    #[inline]
+    fn exact_pair(
+        &self,
+        _: usize,
+        v0_ref: &[F; D],
+        _: usize,
+        v1_ref: &[F; D],
+        write_to_0: &mut [F; D],
+        write_to_1: &mut [F; D],
+    ) {
+        let contribution = (self.0)(v0_ref, v1_ref);
+        for d in 0..D {
+            write_to_0[d] += contribution[d];
+            write_to_1[d] -= contribution[d];
+        }
+    }
+}
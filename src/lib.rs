@@ -1,23 +1,46 @@
 #![doc = include_str!("../ReadMe.md")]
 
-const DEFAULT_BR_LIMIT: Fnum = 1e-8;
+const DEFAULT_BR_LIMIT: f64 = 1e-8;
 
-type Fnum = f64;
 type Udim = usize;
 
-mod colvec;
+mod fnum;
+
+pub use fnum::Fnum;
+
+mod kernel;
+
+pub use kernel::{Kernel, PairKernel, SymmetricKernel};
 
-use std::collections::VecDeque;
+use kernel::IndexedKernel;
+
+mod criterion;
+
+pub use criterion::{NodeSummary, OpeningCriterion};
+
+mod colvec;
 
 use colvec::ColVec;
 
 mod boundbox;
 
+mod charge;
+
+#[cfg(feature = "quadrupole")]
+mod moment;
+
 use boundbox::BoundBox;
 
 mod nodes;
 use nodes::{Internal, Leaf, NodeIndex};
 
+/// A value's position, and the index of its leaf and its index inside the leaf.
+type ValueEntry<const D: Udim, F> = (ColVec<D, F>, Option<(usize, usize)>);
+
+mod node_ref;
+
+pub use node_ref::NodeRef;
+
 /// # Barnes-Hut Tree
 ///
 /// Zhifeng's implementation of Barnes-Hut Tree for accelerated N-body force calculation.
@@ -51,23 +74,25 @@ use nodes::{Internal, Leaf, NodeIndex};
 /// // Calculating the approximated displacement acting on value 0.
 /// bht.calc_force_on_value(0, &is_super_fn, &calc_fn, &mut ans_displacement);
 /// ```
-pub struct BarnesHutTree<const D: Udim> {
-    vs: Vec<Box<(ColVec<D>, Option<(usize, usize)>)>>,
+pub struct BarnesHutTree<const D: Udim, F: Fnum = f64> {
+    vs: Vec<Box<ValueEntry<D, F>>>,
+    ws: Vec<F>,
+    qs: Vec<F>,
 
-    leaf_vec: Vec<Box<Leaf<D>>>,
-    internal_vec: Vec<Box<Internal<D>>>,
+    leaf_vec: Vec<Box<Leaf<D, F>>>,
+    internal_vec: Vec<Box<Internal<D, F>>>,
 
     root: Option<NodeIndex>,
 
-    bb: BoundBox<D>,
+    bb: BoundBox<D, F>,
 
-    br_limit: Fnum,
+    br_limit: F,
 }
 
 mod imple;
 
 /// # Constructors
-impl<const D: Udim> BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
     ///
     /// Construct a new, empty Barnes-Hut Tree.
     ///
@@ -96,11 +121,13 @@ impl<const D: Udim> BarnesHutTree<D> {
         let internal_vec = Vec::new();
         Self {
             vs: Vec::new(),
+            ws: Vec::new(),
+            qs: Vec::new(),
             leaf_vec,
             internal_vec,
             root: None,
-            bb: BoundBox::new_with_arr(&[0.0; D], 1.0),
-            br_limit: DEFAULT_BR_LIMIT,
+            bb: BoundBox::new_with_arr(&[F::ZERO; D], F::ONE),
+            br_limit: F::from_f64(DEFAULT_BR_LIMIT),
         }
     }
 
@@ -131,8 +158,13 @@ impl<const D: Udim> BarnesHutTree<D> {
     /// assert_eq!(ans_displacement, [(-2.0 * 0.2) / (2.0 * 2.0), 0.0],"The results should be the same because super nodes were ignored.");
     /// ```
     ///
-    pub fn with_bounding_and_capacity(root_bc: &[Fnum; D], root_br: Fnum, len: usize) -> Self {
-        Self::with_bounding_and_capacity_and_limit(root_bc, root_br, len, DEFAULT_BR_LIMIT)
+    pub fn with_bounding_and_capacity(root_bc: &[F; D], root_br: F, len: usize) -> Self {
+        Self::with_bounding_and_capacity_and_limit(
+            root_bc,
+            root_br,
+            len,
+            F::from_f64(DEFAULT_BR_LIMIT),
+        )
     }
 
     /// Construct a new Barnes-Hut Tree with specified:
@@ -164,17 +196,19 @@ impl<const D: Udim> BarnesHutTree<D> {
     /// ```
     ///
     pub fn with_bounding_and_capacity_and_limit(
-        root_bc: &[Fnum; D],
-        root_br: Fnum,
+        root_bc: &[F; D],
+        root_br: F,
         len: usize,
-        br_limit: Fnum,
+        br_limit: F,
     ) -> Self {
         assert!(
-            br_limit.is_finite() && br_limit > 0.0,
+            br_limit.is_finite() && br_limit > F::ZERO,
             "The limit should be finite and greater than zero."
         );
         Self {
             vs: Vec::with_capacity(len),
+            ws: Vec::with_capacity(len),
+            qs: Vec::with_capacity(len),
             leaf_vec: Vec::with_capacity(len),
             internal_vec: Vec::with_capacity(len),
             root: None,
@@ -186,6 +220,8 @@ impl<const D: Udim> BarnesHutTree<D> {
     /// - the initial bounding hypercube center and radius (half-width),
     /// - the to-insert values (bodies).
     ///
+    /// The tree is built in bulk, which is faster than adding the values one by one but results in the same tree. If the bounding hypercube does not contain all the values, it is first expanded to the hypercube the root would end at when adding them one by one.
+    ///
     /// ## Example:
     ///
     /// ```rust
@@ -206,15 +242,10 @@ impl<const D: Udim> BarnesHutTree<D> {
     ///     "The results should be the same because super nodes were ignored.");
     /// ```
     ///
-    pub fn with_bounding_and_values(
-        root_bc: &[Fnum; D],
-        root_br: Fnum,
-        vals: &[[Fnum; D]],
-    ) -> Self {
-        let mut temp_self = Self::new_without_add(root_bc, root_br, vals, DEFAULT_BR_LIMIT);
-        for i in 0..vals.len() {
-            temp_self.add(i);
-        }
+    pub fn with_bounding_and_values(root_bc: &[F; D], root_br: F, vals: &[[F; D]]) -> Self {
+        let mut temp_self =
+            Self::new_without_add(root_bc, root_br, vals, F::from_f64(DEFAULT_BR_LIMIT));
+        temp_self.add_all();
         temp_self
     }
 
@@ -223,6 +254,8 @@ impl<const D: Udim> BarnesHutTree<D> {
     /// - the to-insert values (bodies),
     /// - the minimum "radius" (half-width) of the hypercube.
     ///
+    /// Same as [BarnesHutTree::with_bounding_and_values], the tree is built in bulk.
+    ///
     /// ## Example:
     ///
     /// ```
@@ -249,16 +282,70 @@ impl<const D: Udim> BarnesHutTree<D> {
     /// ```
     ///
     pub fn with_bounding_and_values_and_limit(
-        root_bc: &[Fnum; D],
-        root_br: Fnum,
-        vals: &[[Fnum; D]],
-        br_limit: Fnum,
+        root_bc: &[F; D],
+        root_br: F,
+        vals: &[[F; D]],
+        br_limit: F,
     ) -> Self {
-        let num = vals.len();
         let mut temp_self = Self::new_without_add(root_bc, root_br, vals, br_limit);
-        for i in 0..num {
-            temp_self.add(i);
-        }
+        temp_self.add_all();
+        temp_self
+    }
+
+    /// Construct a new Barnes-Hut Tree with the to-insert values (bodies).
+    ///
+    /// Instead of guessing the initial bounding hypercube, the tree uses the minimal hypercube enclosing all the values, so the root does not need to expand, and the tree is built in bulk. If there are no values, the tree is the same as [BarnesHutTree::new].
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[-1.0,1.0],[1.0,1.0]]);
+    ///
+    /// let mut ans_displacement = [0.0; 2];
+    ///
+    /// let is_super_fn = |_: &[f64;2],_:&[f64;2],_:f64| -> bool {false}; // ignoring super nodes
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    ///
+    /// bht.calc_force_on_value(0, &is_super_fn, &calc_fn, &mut ans_displacement);
+    ///
+    /// assert_eq!(ans_displacement, [(-2.0 * 0.2) / (2.0 * 2.0), 0.0],
+    ///     "The results should be the same because super nodes were ignored.");
+    /// assert_eq!(bht.get_total_nodes_num(), 3);
+    /// ```
+    ///
+    pub fn from_values(vals: &[[F; D]]) -> Self {
+        Self::with_values_and_limit(vals, F::from_f64(DEFAULT_BR_LIMIT))
+    }
+
+    /// Construct a new Barnes-Hut Tree with specified:
+    /// - the to-insert values (bodies),
+    /// - the minimum "radius" (half-width) of the hypercube.
+    ///
+    /// Same as [BarnesHutTree::from_values], the tree uses the minimal hypercube enclosing all the values.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// // Setting the minimum allowed "radius" of the hypercube to be 100.0.
+    /// let bht: BHTree<2> = BHTree::with_values_and_limit(&[[-1.0,1.0],[1.0,1.0]], 100.0);
+    ///
+    /// assert_eq!(bht.get_total_nodes_num(), 1,
+    ///     "The total number of nodes inside the tree should be one since the limit is 100.0.")
+    /// ```
+    ///
+    pub fn with_values_and_limit(vals: &[[F; D]], br_limit: F) -> Self {
+        let bb = BoundBox::new_enclosing(vals.iter(), br_limit)
+            .unwrap_or_else(|| BoundBox::new_with_arr(&[F::ZERO; D], F::ONE));
+        let mut temp_self = Self::new_without_add(&bb.bc.data, bb.br, vals, br_limit);
+        temp_self.build();
         temp_self
     }
 
@@ -267,11 +354,13 @@ impl<const D: Udim> BarnesHutTree<D> {
     /// This method takes:
     /// - an index of the target value
     /// - a closure to determine whether a super node is "far" enough to be considered as a whole,
-    ///     The closure takes the current target value, the position of the body, and the bounding box center and radius to determine whether the super node is "far" enough.
-    /// - a closure to calculate force or other relations between the target value and another super node (or value if the size is one),
-    ///     The closure takes the target value, the mean position of a group of values, the size of the group, and the answer's mutable reference.
+    ///   The closure takes the current target value, the average position of the values in the super node, and the bounding box radius. To use a criterion seeing the whole [NodeSummary], see [BarnesHutTree::calc_force_on_value_with_criterion].
+    /// - a [Kernel] to calculate force or other relations between the target value and another super node (or value if the size is one),
+    ///   It can be a closure taking the target value, the mean position of a group of values, the size of the group, and the answer's mutable reference, or a type telling [Kernel::exact] calculations on single values from [Kernel::approx] ones on super nodes.
     /// - a custom struct to store and accumulate the results from the previous calculator closure.
     ///
+    /// The other values sharing a leaf with the target are calculated one by one with a size of one. Earlier versions passed the size of the whole leaf for each of them, so the results differ from theirs whenever `br_limit` lets several values share a leaf.
+    ///
     /// ## Example:
     ///
     /// ```rust
@@ -295,91 +384,125 @@ impl<const D: Udim> BarnesHutTree<D> {
     pub fn calc_force_on_value<T>(
         &self,
         value_i: usize,
-        is_super_node: impl Fn(&[Fnum; D], &[Fnum; D], Fnum) -> bool,
-        calc_fn: impl Fn(&[Fnum; D], &[Fnum; D], usize, &mut T),
+        is_super_node: impl Fn(&[F; D], &[F; D], F) -> bool,
+        calc_fn: impl Kernel<D, F, T>,
+        write_to_value: &mut T,
+    ) -> bool {
+        self.calc_force_on_value_with_criterion(value_i, is_super_node, calc_fn, write_to_value)
+    }
+
+    /// The same as [BarnesHutTree::calc_force_on_value], but taking an [OpeningCriterion] to determine whether a super node is "far" enough, for example, a criterion from [utils] seeing the whole [NodeSummary].
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
+    ///
+    /// let mut ans_displacement = [0.0; 2];
+    ///
+    /// let criterion = zbht::utils::factory_of_min_distance_criterion::<2, f64>(0.5);
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    ///
+    /// bht.calc_force_on_value_with_criterion(0, criterion, &calc_fn, &mut ans_displacement);
+    ///
+    /// assert_eq!(ans_displacement, [(-2.0 * 0.2) / (2.0 * 2.0), 0.0]);
+    /// ```
+    ///
+    pub fn calc_force_on_value_with_criterion<T>(
+        &self,
+        value_i: usize,
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Kernel<D, F, T>,
         write_to_value: &mut T,
     ) -> bool {
         if value_i >= self.vs.len() {
             return false;
         }
 
-        let mut curr_info =
-            self.calc_leaf_siblings_and_get_parent(value_i, &calc_fn, write_to_value);
-
-        let mut q: VecDeque<&NodeIndex> = VecDeque::with_capacity(self.get_total_nodes_num() / 2);
-        let curr_v_ref = &self.vs[value_i].0.data;
-
-        while let Some((curr_internal_i, curr_in_leaf_i)) = curr_info {
-            let curr_internal_ref = self.internal_vec[curr_internal_i].as_ref();
-            for (in_leaf_i, node_opt) in curr_internal_ref.nexts.iter().enumerate() {
-                if in_leaf_i == curr_in_leaf_i {
-                    continue;
-                }
-                if let Some(curr_node_box_ref) = node_opt.as_ref() {
-                    self.calc_node(
-                        curr_v_ref,
-                        curr_node_box_ref,
-                        &mut q,
-                        write_to_value,
-                        &is_super_node,
-                        &calc_fn,
-                    )
-                }
-            }
-            curr_info = curr_internal_ref.parent;
-        }
-
-        while let Some(curr_node_box_ref) = q.pop_front() {
-            self.calc_node(
-                curr_v_ref,
-                curr_node_box_ref,
-                &mut q,
-                write_to_value,
-                &is_super_node,
-                &calc_fn,
-            )
-        }
+        self.calc_interactions_on_value(
+            value_i,
+            &is_super_node,
+            |curr_v_ref, other, write_to| other.calc_with_kernel(curr_v_ref, &calc_fn, write_to),
+            write_to_value,
+        );
         true
     }
-}
 
-/// # Utilities
-///
-/// These methods are about getting, pushing, removing, and updating values (bodies) inside the tree.
-impl<const D: Udim> BarnesHutTree<D> {
-    /// Get a reference of the stored value's coordinates.
+    /// Calculate force or custom relationships between selected super nodes on a specific target value (body), passing the indices of both values to the exact calculations.
+    ///
+    /// This method is the same as [BarnesHutTree::calc_force_on_value], but the calculation is split into two closures:
+    /// - `exact_fn` for another single value, which takes the index and the position of the target, the index and the position of the other value, and the answer's mutable reference. The indices can be used to look up per-value attributes, like charges, node types, or graph adjacency to skip connected pairs.
+    /// - `approx_fn` for a "far" super node as a whole, which is the same as the closure form of `calc_fn` in [BarnesHutTree::calc_force_on_value].
     ///
     /// ## Return
     ///
-    /// This method returns a reference of the current value (body)'s coordinates if the index is within-range.
+    /// This method returns `false` without calculating anything if the target index is out of range.
     ///
-    /// ## Example
+    /// ## Example:
     ///
     /// ```rust
     /// use zhifeng_impl_barnes_hut_tree as zbht;
     ///
     /// use zbht::BarnesHutTree as BHTree;
     ///
-    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0],[1.0,-1.0]]);
     ///
-    /// assert_eq!(bht.get(0), Some(&[-1.0,1.0]));
-    /// assert_eq!(bht.get(2), None);
+    /// let is_super_fn = |_: &[f64; 2],_: &[f64; 2],_: f64| -> bool {false};
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    ///
+    /// // The values 0 and 2 are connected, so they do not repel each other.
+    /// let exact_fn = |target_i: usize, curr_v: &[f64; 2], other_i: usize, other_v: &[f64; 2], ans: &mut [f64; 2]| {
+    ///     if target_i + other_i != 2 {
+    ///         calc_fn(curr_v, other_v, 1, ans);
+    ///     }
+    /// };
+    ///
+    /// let mut ans_displacement = [0.0; 2];
+    /// assert!(bht.calc_force_on_value_with_indices(0, &is_super_fn, &exact_fn, &calc_fn, &mut ans_displacement));
+    /// assert_eq!(ans_displacement, [(-2.0 * 0.2) / (2.0 * 2.0), 0.0]);
+    ///
+    /// assert!(!bht.calc_force_on_value_with_indices(3, &is_super_fn, &exact_fn, &calc_fn, &mut ans_displacement));
     /// ```
     ///
-    pub fn get(&self, value_i: usize) -> Option<&[Fnum; D]> {
+    pub fn calc_force_on_value_with_indices<T>(
+        &self,
+        value_i: usize,
+        is_super_node: impl OpeningCriterion<D, F>,
+        exact_fn: impl Fn(usize, &[F; D], usize, &[F; D], &mut T),
+        approx_fn: impl Fn(&[F; D], &[F; D], usize, &mut T),
+        write_to_value: &mut T,
+    ) -> bool {
         if value_i >= self.vs.len() {
-            return None;
+            return false;
         }
-        Some(&self.vs[value_i].0.data)
+
+        let kernel = IndexedKernel {
+            target_i: value_i,
+            exact_fn,
+            approx_fn,
+        };
+        self.calc_interactions_on_value(
+            value_i,
+            &is_super_node,
+            |curr_v_ref, other, write_to| other.calc_with_kernel(curr_v_ref, &kernel, write_to),
+            write_to_value,
+        );
+        true
     }
 
-    /// Push a value into the tree.
+    /// Calculate force or displacement between selected super nodes on a specific target value (body), excluding some other values.
     ///
-    /// ## Return
+    /// This method is the same as [BarnesHutTree::calc_force_on_value], but the values in `excluded`, like the bonded or adjacent ones, do not act on the target. An excluded value calculated directly is skipped. When an excluded value is inside an accepted super node, its exact contribution is subtracted from the result to correct the approximation.
     ///
-    /// This method will return the value's corresponding value-index `usize` in the tree.
+    /// Therefore, unlike its siblings generic over the result type, this method only writes to a vector `[F; D]`: a [Kernel] only knows how to add a contribution, and the correction needs to take one away. For other result types, [BarnesHutTree::calc_force_on_value_with_indices] can skip the excluded pairs in `exact_fn`, although the super nodes containing them are not corrected.
     ///
-    /// This design is mainly for easier mapping of values in case we want to connect the values with other format of keys, for example, `String` or `usize` that is not filling an entire range from `0..len`.
+    /// ## Return
+    ///
+    /// This method returns `false` without calculating anything if the target index or an excluded index is out of range.
     ///
     /// ## Example:
     ///
@@ -388,163 +511,1495 @@ impl<const D: Udim> BarnesHutTree<D> {
     ///
     /// use zbht::BarnesHutTree as BHTree;
     ///
-    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],4.0, &[[-3.0,3.0],[3.0,3.0],[3.5,3.5]]);
     ///
-    /// bht.push(&[-1.0,1.0]);
-    /// let idx = bht.push(&[1.0,1.0]);
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
     ///
-    /// assert_eq!(bht.get(0), Some(&[-1.0,1.0]));
-    /// assert_eq!(bht.get(idx), Some(&[1.0,1.0]));
+    /// // The values 1 and 2 are far enough to be a super node, so the value 1 is subtracted from the super node.
+    /// let mut approx_displacement = [0.0; 2];
+    /// calc_fn(&[-3.0,3.0], &[3.25,3.25], 2, &mut approx_displacement);
+    /// let mut excluded_displacement = [0.0; 2];
+    /// calc_fn(&[-3.0,3.0], &[3.0,3.0], 1, &mut excluded_displacement);
+    /// let expected_displacement = [
+    ///     approx_displacement[0] - excluded_displacement[0],
+    ///     approx_displacement[1] - excluded_displacement[1],
+    /// ];
+    ///
+    /// let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(1.2);
+    /// let mut ans_displacement = [0.0; 2];
+    /// assert!(bht.calc_force_on_value_excluding(0, &[1], &is_super_fn, &calc_fn, &mut ans_displacement));
+    /// assert!((ans_displacement[0] - expected_displacement[0]).abs() < 1e-9);
+    /// assert!((ans_displacement[1] - expected_displacement[1]).abs() < 1e-9);
+    ///
+    /// assert!(!bht.calc_force_on_value_excluding(0, &[3], &is_super_fn, &calc_fn, &mut ans_displacement));
     /// ```
     ///
-    pub fn push(&mut self, value_ref: &[Fnum; D]) -> usize {
-        let value_i = self.vs.len();
-        self.vs
-            .push(Box::new((ColVec::new_with_arr(value_ref), None)));
+    pub fn calc_force_on_value_excluding(
+        &self,
+        value_i: usize,
+        excluded: &[usize],
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Kernel<D, F, [F; D]>,
+        write_to_value: &mut [F; D],
+    ) -> bool {
+        if value_i >= self.vs.len() || excluded.iter().any(|other_i| *other_i >= self.vs.len()) {
+            return false;
+        }
 
-        self.add(value_i);
-        value_i
+        self.calc_interactions_on_value_excluding(
+            value_i,
+            excluded,
+            &is_super_node,
+            &calc_fn,
+            write_to_value,
+        );
+        true
     }
 
-    /// Update the coordinates of a value.
+    /// Calculate force or custom relationships between selected super nodes on a specific target value (body), estimating the approximation error alongside.
+    ///
+    /// This method is the same as [BarnesHutTree::calc_force_on_value], but for each accepted super node, `error_fn` takes the target value and the [NodeSummary] of the super node, and returns the estimated error of treating the node as a whole, which is added to `error_to_value`. Values calculated directly add no error. For example, [utils::factory_of_repulsive_displacement_error_fn] estimates the error of [utils::factory_of_repulsive_displacement_calc_fn].
+    ///
+    /// The accumulated error tells how far the answer might be from the exact one without the exact calculation, so that `theta` can be picked adaptively.
     ///
     /// ## Return
     ///
-    /// This method returns a boolean indicating whether the update is successful. The update will usually be successful if the value index is pointing to a valid value.
+    /// This method returns `false` without calculating anything if the target index is out of range.
+    ///
+    /// ## Example:
     ///
-    /// ## Example
     /// ```rust
     /// use zhifeng_impl_barnes_hut_tree as zbht;
     ///
     /// use zbht::BarnesHutTree as BHTree;
     ///
-    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],4.0, &[[-3.0,3.0],[3.0,3.0],[3.5,3.5]]);
     ///
-    /// bht.push(&[-0.5,1.0]);
-    /// let idx = bht.push(&[1.0,1.0]);
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    /// let error_fn = zbht::utils::factory_of_repulsive_displacement_error_fn::<2, f64>(1.0, 0.2);
     ///
-    /// bht.update(0, &[-1.0,1.0]);
+    /// let mut ans_displacement = [0.0; 2];
+    /// let mut ans_error = 0.0;
+    /// let is_super_fn = |_: &[f64; 2],_: &[f64; 2],_: f64| -> bool {false};
+    /// assert!(bht.calc_force_on_value_with_error(0, &is_super_fn, &calc_fn, &error_fn, &mut ans_displacement, &mut ans_error));
+    /// assert_eq!(ans_error, 0.0, "No super nodes, no errors.");
     ///
-    /// assert_eq!(bht.get(0), Some(&[-1.0,1.0]));
-    /// assert_eq!(bht.get(idx), Some(&[1.0,1.0]));
+    /// let mut ans_displacement = [0.0; 2];
+    /// let mut ans_error = 0.0;
+    /// let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(1.2);
+    /// assert!(bht.calc_force_on_value_with_error(0, &is_super_fn, &calc_fn, &error_fn, &mut ans_displacement, &mut ans_error));
+    /// assert!(ans_error > 0.0);
     /// ```
     ///
-    pub fn update(&mut self, value_i: usize, value_ref: &[Fnum; D]) -> bool {
-        let len = self.vs.len();
-        if value_i >= len {
+    pub fn calc_force_on_value_with_error<T>(
+        &self,
+        value_i: usize,
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Kernel<D, F, T>,
+        error_fn: impl Fn(&[F; D], &NodeSummary<'_, D, F>) -> F,
+        write_to_value: &mut T,
+        error_to_value: &mut F,
+    ) -> bool {
+        if value_i >= self.vs.len() {
             return false;
         }
-        self.sub(value_i);
-        debug_assert!(
-            self.vs[value_i].1.is_none(),
-            "The to_leaf index should be `None`."
+
+        self.calc_interactions_on_value(
+            value_i,
+            &is_super_node,
+            |curr_v_ref, other, (write_to, error_to): &mut (&mut T, &mut F)| {
+                if let Some(node) = other.get_summary() {
+                    **error_to += error_fn(curr_v_ref, &node);
+                }
+                other.calc_with_kernel(curr_v_ref, &calc_fn, *write_to)
+            },
+            &mut (write_to_value, error_to_value),
         );
-        self.vs[value_i].0.clone_from_arr_ref(value_ref);
-        self.add(value_i);
         true
     }
 
-    /// Remove a value (body) from the tree.
+    /// Calculate force or custom relationships between selected super nodes on an arbitrary point, which does not need to be a value (body) in the tree.
+    ///
+    /// This method is useful for probing the field, for example, sampling a grid for a heatmap, or calculating on a dragged value before inserting it. Different from [BarnesHutTree::calc_force_on_value], the tree is traversed from the root. The closures are the same as the ones of [BarnesHutTree::calc_force_on_value].
+    ///
+    /// If `exclude_value_i` is a value's index, that value is skipped, and the nodes containing it are never considered as super nodes. For example, passing a value's own position and index calculates the same interactions as [BarnesHutTree::calc_force_on_value], except for the summation order.
     ///
     /// ## Return
     ///
-    /// Since the underlying structure uses `vec` to store nodes, if a value that is not the last one in the `vec` needs to be removed, the last value from the `vec` will replace its position. The method returns an option of an index `usize` to indicate which value was moved to that position to replace the removed one (always the previous last one). If the to-remove value is the last one, or the index is out-of-range, the method will return a `None`.
+    /// This method returns `false` without calculating anything if the excluded index is out of range.
+    ///
+    /// ## Example:
     ///
-    /// ## Example
     /// ```rust
     /// use zhifeng_impl_barnes_hut_tree as zbht;
     ///
     /// use zbht::BarnesHutTree as BHTree;
     ///
-    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
     ///
-    /// bht.push(&[-0.5,1.0]);
-    /// bht.push(&[1.0,1.0]);
+    /// let is_super_fn = |_: &[f64; 2],_: &[f64; 2],_: f64| -> bool {false};
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
     ///
-    /// let old_i = bht.remove(0);
+    /// let mut ans_displacement = [0.0; 2];
+    /// assert!(bht.calc_force_at_point(&[0.0,1.0], None, &is_super_fn, &calc_fn, &mut ans_displacement));
+    /// assert_eq!(ans_displacement, [0.0, 0.0], "The two values push the point towards opposite directions.");
     ///
-    /// assert_eq!(old_i, Some(1));
-    /// assert_eq!(bht.get(0).unwrap(), &[1.0,1.0]);
-    /// assert_eq!(bht.remove(1), None);
+    /// let mut ans_displacement = [0.0; 2];
+    /// assert!(bht.calc_force_at_point(&[-1.0,1.0], Some(0), &is_super_fn, &calc_fn, &mut ans_displacement));
+    /// assert_eq!(ans_displacement, [(-2.0 * 0.2) / (2.0 * 2.0), 0.0]);
+    ///
+    /// assert!(!bht.calc_force_at_point(&[-1.0,1.0], Some(2), &is_super_fn, &calc_fn, &mut ans_displacement));
     /// ```
     ///
-    pub fn remove(&mut self, value_i: usize) -> Option<usize> {
-        let last_i = self.vs.len() - 1;
-        if value_i > last_i {
-            return None;
+    pub fn calc_force_at_point<T>(
+        &self,
+        point: &[F; D],
+        exclude_value_i: Option<usize>,
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Kernel<D, F, T>,
+        write_to: &mut T,
+    ) -> bool {
+        if exclude_value_i.is_some_and(|value_i| value_i >= self.vs.len()) {
+            return false;
         }
-        self.sub(value_i);
-        let last_v_opt = self.vs.pop().expect("Should have a last");
-        if value_i < last_i {
-            if let Some((leaf_i, in_leaf_i)) = last_v_opt.1 {
-                #[cfg(not(feature = "unchecked"))]
-                {
-                    *self
-                        .leaf_vec
-                        .get_mut(leaf_i)
-                        .expect("To update the leaf node's value pointing index to the new value location; The leaf should be valid")
-                        .vs
-                        .get_mut(in_leaf_i)
-                        .expect("To update the leaf node's value pointing index to the new value location; The in-leaf position should be valid") = value_i;
-                }
 
-                #[cfg(feature = "unchecked")]
-                {
-                    unsafe {
-                        *self
-                            .leaf_vec
-                            .get_unchecked_mut(leaf_i)
-                            .vs
-                            .get_unchecked_mut(in_leaf_i) = value_i;
-                    }
-                }
-            }
-            self.vs[value_i] = last_v_opt;
-            Some(last_i)
-        } else {
-            None
-        }
+        self.calc_interactions_on_point(
+            point,
+            exclude_value_i,
+            &is_super_node,
+            |curr_v_ref, other, write_to| other.calc_with_kernel(curr_v_ref, &calc_fn, write_to),
+            write_to,
+        );
+        true
     }
 
-    /// Get the total number of nodes
+    /// Calculate force or custom relationships between selected super nodes on all the values (bodies) in one call.
+    ///
+    /// This method is the same as calling [BarnesHutTree::calc_force_on_value] for each value, with the results accumulated into the corresponding slot of `write_to`, which should have one slot for each value. But the values are visited leaf by leaf in the spatial order, so neighbouring values go through similar nodes one after another, and the traversal buffers are allocated only once.
     ///
     /// ## Return
     ///
-    /// This method returns the total number of nodes in the tree.
+    /// This method returns `false` without calculating anything if the length of `write_to` is not the number of values.
     ///
-    /// ## Example
+    /// ## Example:
     ///
     /// ```rust
     /// use zhifeng_impl_barnes_hut_tree as zbht;
     ///
     /// use zbht::BarnesHutTree as BHTree;
     ///
-    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
-    ///
-    /// assert_eq!(bht.get_total_nodes_num(), 0);
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
     ///
-    /// bht.push(&[-1.0,1.0]);
-    /// assert_eq!(bht.get_total_nodes_num(), 1);
+    /// let mut ans_displacements = vec![[0.0; 2]; 2];
     ///
-    /// bht.push(&[1.0,1.0]);
-    /// assert_eq!(bht.get_total_nodes_num(), 3);
+    /// let is_super_fn = |_: &[f64; 2],_: &[f64; 2],_: f64| -> bool {false};
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
     ///
-    /// bht.remove(0);
-    /// assert_eq!(bht.get_total_nodes_num(), 1);
+    /// assert!(bht.calc_force_on_all_values(&is_super_fn, &calc_fn, &mut ans_displacements));
     ///
-    /// bht.remove(0);
-    /// assert_eq!(bht.get_total_nodes_num(), 0);
+    /// assert_eq!(ans_displacements, vec![[(-2.0 * 0.2) / (2.0 * 2.0), 0.0], [(2.0 * 0.2) / (2.0 * 2.0), 0.0]]);
+    /// assert!(!bht.calc_force_on_all_values(&is_super_fn, &calc_fn, &mut ans_displacements[..1]));
+    /// ```
+    ///
+    pub fn calc_force_on_all_values<T>(
+        &self,
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Kernel<D, F, T>,
+        write_to: &mut [T],
+    ) -> bool {
+        if write_to.len() != self.vs.len() {
+            return false;
+        }
+
+        self.calc_interactions_on_all_values(
+            &is_super_node,
+            |curr_v_ref, other, write_to| other.calc_with_kernel(curr_v_ref, &calc_fn, write_to),
+            write_to,
+        );
+        true
+    }
+
+    /// Calculate force or custom relationships between selected super nodes on all the values (bodies), estimating the approximation errors alongside.
+    ///
+    /// This method is the same as [BarnesHutTree::calc_force_on_all_values], but the error of each value is estimated and added to `error_to` in the same way as [BarnesHutTree::calc_force_on_value_with_error].
+    ///
+    /// ## Return
+    ///
+    /// This method returns `false` without calculating anything if the length of `write_to` or `error_to` is not the number of values.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],4.0, &[[-3.0,3.0],[3.0,3.0],[3.5,3.5]]);
+    ///
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    /// let error_fn = zbht::utils::factory_of_repulsive_displacement_error_fn::<2, f64>(1.0, 0.2);
+    /// let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(1.2);
+    ///
+    /// let mut ans_displacements = vec![[0.0; 2]; 3];
+    /// let mut ans_errors = vec![0.0; 3];
+    /// assert!(bht.calc_force_on_all_values_with_error(&is_super_fn, &calc_fn, &error_fn, &mut ans_displacements, &mut ans_errors));
+    /// assert!(ans_errors[0] > 0.0);
+    ///
+    /// let mut ans_displacement = [0.0; 2];
+    /// let mut ans_error = 0.0;
+    /// bht.calc_force_on_value_with_error(0, &is_super_fn, &calc_fn, &error_fn, &mut ans_displacement, &mut ans_error);
+    /// assert_eq!(ans_displacements[0], ans_displacement);
+    /// assert_eq!(ans_errors[0], ans_error);
+    ///
+    /// assert!(!bht.calc_force_on_all_values_with_error(&is_super_fn, &calc_fn, &error_fn, &mut ans_displacements, &mut ans_errors[..2]));
+    /// ```
+    ///
+    pub fn calc_force_on_all_values_with_error<T>(
+        &self,
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Kernel<D, F, T>,
+        error_fn: impl Fn(&[F; D], &NodeSummary<'_, D, F>) -> F,
+        write_to: &mut [T],
+        error_to: &mut [F],
+    ) -> bool {
+        if write_to.len() != self.vs.len() || error_to.len() != self.vs.len() {
+            return false;
+        }
+
+        let mut write_to_pairs: Vec<(&mut T, &mut F)> =
+            write_to.iter_mut().zip(error_to.iter_mut()).collect();
+        self.calc_interactions_on_all_values(
+            &is_super_node,
+            |curr_v_ref, other, (write_to, error_to): &mut (&mut T, &mut F)| {
+                if let Some(node) = other.get_summary() {
+                    **error_to += error_fn(curr_v_ref, &node);
+                }
+                other.calc_with_kernel(curr_v_ref, &calc_fn, *write_to)
+            },
+            &mut write_to_pairs,
+        );
+        true
+    }
+
+    /// Calculate force or custom relationships between selected super nodes on all the values (bodies) with multiple threads.
+    ///
+    /// This method is the same as [BarnesHutTree::calc_force_on_all_values], except that the values are split into chunks and calculated on scoped worker threads, one for each available CPU. Therefore, the closures should be `Sync`, and the results should be `Send`. Each value is calculated in the same way as the sequential version, so the results are the same bit for bit.
+    ///
+    /// ## Return
+    ///
+    /// This method returns `false` without calculating anything if the length of `write_to` is not the number of values.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
+    ///
+    /// let mut ans_displacements = vec![[0.0; 2]; 2];
+    ///
+    /// let is_super_fn = |_: &[f64; 2],_: &[f64; 2],_: f64| -> bool {false};
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    ///
+    /// assert!(bht.calc_force_on_all_values_in_parallel(&is_super_fn, &calc_fn, &mut ans_displacements));
+    ///
+    /// assert_eq!(ans_displacements, vec![[(-2.0 * 0.2) / (2.0 * 2.0), 0.0], [(2.0 * 0.2) / (2.0 * 2.0), 0.0]]);
+    /// ```
+    ///
+    #[cfg(feature = "parallel")]
+    pub fn calc_force_on_all_values_in_parallel<T: Send>(
+        &self,
+        is_super_node: impl OpeningCriterion<D, F> + Sync,
+        calc_fn: impl Kernel<D, F, T> + Sync,
+        write_to: &mut [T],
+    ) -> bool {
+        if write_to.len() != self.vs.len() {
+            return false;
+        }
+
+        self.calc_interactions_on_all_values_in_parallel(
+            &is_super_node,
+            |curr_v_ref, other, write_to| other.calc_with_kernel(curr_v_ref, &calc_fn, write_to),
+            write_to,
+        );
+        true
+    }
+
+    /// Calculate force or custom relationships on all the values (bodies) with a dual-tree traversal over pairs of nodes.
+    ///
+    /// Instead of walking the tree once for each value, the pairs of nodes are walked from the root. When `is_well_separated` accepts a pair of nodes, given the average positions and the half widths of both nodes, the two nodes interact once in each direction: `calc_fn` is called on the average position of one node with the average position and the number of values of the other one, the same as a super node in [BarnesHutTree::calc_force_on_value]. Pairs of values in leaves that are not well-separated interact value by value.
+    ///
+    /// The result of an accepted node is evaluated once at the node's average position and copied unchanged into every value inside it. There is no local (Taylor) expansion moving the result to each value's own position, so all the values in an accepted node get the same contribution, and the error grows with the width of the node on the receiving side as well. That is the price of the saved work: for the repulsive displacement on 1000 uniformly random values with [utils::factory_of_is_well_separated_fn] at `theta = 0.5`, the relative error over all the values is about 3%, checked to be under 5% in the tests, while [BarnesHutTree::calc_force_on_all_values] with [utils::factory_of_is_super_node_fn] at the same `theta` stays under 0.3%. Use a smaller `theta`, or the per-value methods, when the values need their own far-field results.
+    ///
+    /// The node results are merged into the values' slots of `write_to` by `merge_fn`, together with the exact ones, starting from `zero`. The slots are accumulated, and `write_to` should have one slot for each value.
+    ///
+    /// ## Return
+    ///
+    /// This method returns `false` without calculating anything if the length of `write_to` is not the number of values.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
+    ///
+    /// let mut ans_displacements = vec![[0.0; 2]; 2];
+    ///
+    /// let is_well_separated_fn = |_: &[f64; 2],_: f64,_: &[f64; 2],_: f64| -> bool {false};
+    /// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    /// let merge_fn = |acc: &mut [f64; 2], other: &[f64; 2]| {
+    ///     acc[0] += other[0];
+    ///     acc[1] += other[1];
+    /// };
+    ///
+    /// assert!(bht.calc_forces_dual_tree(&is_well_separated_fn, &calc_fn, &merge_fn, [0.0; 2], &mut ans_displacements));
+    ///
+    /// assert_eq!(ans_displacements, vec![[(-2.0 * 0.2) / (2.0 * 2.0), 0.0], [(2.0 * 0.2) / (2.0 * 2.0), 0.0]]);
+    ///
+    /// assert!(!bht.calc_forces_dual_tree(&is_well_separated_fn, &calc_fn, &merge_fn, [0.0; 2], &mut ans_displacements[..1]));
+    /// ```
+    ///
+    pub fn calc_forces_dual_tree<T: Clone>(
+        &self,
+        is_well_separated: impl Fn(&[F; D], F, &[F; D], F) -> bool,
+        calc_fn: impl Kernel<D, F, T>,
+        merge_fn: impl Fn(&mut T, &T),
+        zero: T,
+        write_to: &mut [T],
+    ) -> bool {
+        if write_to.len() != self.vs.len() {
+            return false;
+        }
+
+        self.calc_interactions_dual_tree(
+            is_well_separated,
+            |curr_v_ref, other, write_to| other.calc_with_kernel(curr_v_ref, &calc_fn, write_to),
+            merge_fn,
+            zero,
+            write_to,
+        );
+        true
+    }
+
+    /// Calculate symmetric forces on all the values (bodies) with a dual-tree traversal, calculating each exact pair of values only once.
+    ///
+    /// This method walks the pairs of nodes in the same way as [BarnesHutTree::calc_forces_dual_tree], and well-separated nodes interact through [Kernel::approx] in both directions. But the kernel is a [SymmetricKernel], so that a pair of values that is not well-separated is calculated once by [SymmetricKernel::exact_pair], and the two values receive equal and opposite contributions by Newton's third law. A pair closure returning a vector can be wrapped in [PairKernel].
+    ///
+    /// The node results are merged into the values' slots of `write_to` by `merge_fn`, together with the exact ones, starting from `zero`. The slots are accumulated, and `write_to` should have one slot for each value.
+    ///
+    /// ## Return
+    ///
+    /// This method returns `false` without calculating anything if the length of `write_to` is not the number of values.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::{BarnesHutTree as BHTree, PairKernel};
+    ///
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
+    ///
+    /// let mut ans_displacements = vec![[0.0; 2]; 2];
+    ///
+    /// let is_well_separated_fn = |_: &[f64; 2],_: f64,_: &[f64; 2],_: f64| -> bool {false};
+    /// let kernel = PairKernel(zbht::utils::factory_of_repulsive_displacement_pair_fn::<2, f64>(1.0, 0.2));
+    /// let merge_fn = |acc: &mut [f64; 2], other: &[f64; 2]| {
+    ///     acc[0] += other[0];
+    ///     acc[1] += other[1];
+    /// };
+    ///
+    /// assert!(bht.calc_symmetric_forces_dual_tree(&is_well_separated_fn, &kernel, &merge_fn, [0.0; 2], &mut ans_displacements));
+    ///
+    /// assert_eq!(ans_displacements, vec![[(-2.0 * 0.2) / (2.0 * 2.0), 0.0], [(2.0 * 0.2) / (2.0 * 2.0), 0.0]]);
+    ///
+    /// assert!(!bht.calc_symmetric_forces_dual_tree(&is_well_separated_fn, &kernel, &merge_fn, [0.0; 2], &mut ans_displacements[..1]));
+    /// ```
+    ///
+    pub fn calc_symmetric_forces_dual_tree<T: Clone>(
+        &self,
+        is_well_separated: impl Fn(&[F; D], F, &[F; D], F) -> bool,
+        kernel: &impl SymmetricKernel<D, F, T>,
+        merge_fn: impl Fn(&mut T, &T),
+        zero: T,
+        write_to: &mut [T],
+    ) -> bool {
+        if write_to.len() != self.vs.len() {
+            return false;
+        }
+
+        self.calc_symmetric_interactions_dual_tree(
+            is_well_separated,
+            kernel,
+            merge_fn,
+            zero,
+            write_to,
+        );
+        true
+    }
+
+    /// Calculate the approximate total pairwise potential energy of all the values (bodies), taking the values' weights (masses) into account.
+    ///
+    /// The pairs of nodes are walked in the same way as [BarnesHutTree::calc_forces_dual_tree], so each pair of values is counted exactly once, either through a pair of well-separated nodes or directly. Like the closure of [BarnesHutTree::calc_weighted_force_on_value], `potential_fn` takes a target position, the other position, and the other weight, and adds the potential per unit weight at the target to the answer, for example, [utils::factory_of_plummer_gravity_potential_calc_fn]. The potential is then multiplied by the target's weight, so it should be symmetric in the two values.
+    ///
+    /// For a well-separated pair of nodes, the potential at the first node's center of mass by the second node as a whole is multiplied by the first node's total weight.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0, 0.0], 4.0, 3);
+    /// bht.push_weighted(&[0.0, 0.0], 1.0);
+    /// bht.push_weighted(&[1.0, 0.0], 2.0);
+    /// bht.push_weighted(&[0.0, 2.0], 3.0);
+    ///
+    /// let is_well_separated_fn = |_: &[f64; 2], _: f64, _: &[f64; 2], _: f64| -> bool { false };
+    /// // The potential `-weight / dis`.
+    /// let potential_fn = zbht::utils::factory_of_plummer_gravity_potential_calc_fn::<2, f64>(1.0, 0.0);
+    ///
+    /// let total = bht.calc_total_potential(&is_well_separated_fn, &potential_fn);
+    /// assert!((total - (-1.0 * 2.0 / 1.0 - 1.0 * 3.0 / 2.0 - 2.0 * 3.0 / 5.0_f64.sqrt())).abs() < 1e-12);
+    /// ```
+    ///
+    pub fn calc_total_potential(
+        &self,
+        is_well_separated: impl Fn(&[F; D], F, &[F; D], F) -> bool,
+        potential_fn: impl Fn(&[F; D], &[F; D], F, &mut F),
+    ) -> F {
+        self.calc_total_over_pairs_dual_tree(
+            is_well_separated,
+            |a, b| {
+                let mut potential = F::ZERO;
+                potential_fn(
+                    a.get_vc(),
+                    b.get_vc(),
+                    b.get_values_weight_inside(),
+                    &mut potential,
+                );
+                potential * a.get_values_weight_inside()
+            },
+            |value_a_i, value_b_i| {
+                let mut potential = F::ZERO;
+                potential_fn(
+                    &self.vs[value_a_i].0.data,
+                    &self.vs[value_b_i].0.data,
+                    self.ws[value_b_i],
+                    &mut potential,
+                );
+                potential * self.ws[value_a_i]
+            },
+        )
+    }
+
+    /// Calculate the approximate total pairwise potential energy of all the values (bodies), taking the values' charges into account.
+    ///
+    /// This method is the same as [BarnesHutTree::calc_total_potential], but like the closure of [BarnesHutTree::calc_charged_force_on_value], `potential_fn` takes a target position, and the positive and negative charge centers and total charges of the other side, for example, [utils::factory_of_coulomb_potential_calc_fn]. The potential is then multiplied by the target's charge.
+    ///
+    /// For a well-separated pair of nodes, the potentials at the first node's positive and negative charge centers are multiplied by its positive and negative total charges.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0, 0.0], 4.0, 3);
+    /// bht.push_charged(&[0.0, 0.0], 1.0);
+    /// bht.push_charged(&[1.0, 0.0], -2.0);
+    /// bht.push_charged(&[0.0, 2.0], 3.0);
+    ///
+    /// let is_well_separated_fn = |_: &[f64; 2], _: f64, _: &[f64; 2], _: f64| -> bool { false };
+    /// let potential_fn = zbht::utils::factory_of_coulomb_potential_calc_fn::<2, f64>(1.0);
+    ///
+    /// let total = bht.calc_total_charged_potential(&is_well_separated_fn, &potential_fn);
+    /// assert!((total - (1.0 * -2.0 / 1.0 + 1.0 * 3.0 / 2.0 + -2.0 * 3.0 / 5.0_f64.sqrt())).abs() < 1e-12);
+    /// ```
+    ///
+    pub fn calc_total_charged_potential(
+        &self,
+        is_well_separated: impl Fn(&[F; D], F, &[F; D], F) -> bool,
+        potential_fn: impl Fn(&[F; D], &[F; D], F, &[F; D], F, &mut F),
+    ) -> F {
+        self.calc_total_over_pairs_dual_tree(
+            is_well_separated,
+            |a, b| {
+                let (a_pos_vc, a_pos_q, a_neg_vc, a_neg_q) = a.get_charges();
+                let (b_pos_vc, b_pos_q, b_neg_vc, b_neg_q) = b.get_charges();
+                let mut total = F::ZERO;
+                for (a_vc, a_q) in [(a_pos_vc, a_pos_q), (a_neg_vc, a_neg_q)] {
+                    if a_q != F::ZERO {
+                        let mut potential = F::ZERO;
+                        potential_fn(a_vc, b_pos_vc, b_pos_q, b_neg_vc, b_neg_q, &mut potential);
+                        total += potential * a_q;
+                    }
+                }
+                total
+            },
+            |value_a_i, value_b_i| {
+                let (b_pos_vc, b_pos_q, b_neg_vc, b_neg_q) =
+                    self.calc_value_interaction(value_b_i).get_charges();
+                let mut potential = F::ZERO;
+                potential_fn(
+                    &self.vs[value_a_i].0.data,
+                    b_pos_vc,
+                    b_pos_q,
+                    b_neg_vc,
+                    b_neg_q,
+                    &mut potential,
+                );
+                potential * self.qs[value_a_i]
+            },
+        )
+    }
+
+    /// Calculate force or custom relationships between selected super nodes on a specific target value (body), taking the values' weights (masses) into account.
+    ///
+    /// This method is the same as [BarnesHutTree::calc_force_on_value], except that the calculation closure receives the total weight of a super node (or the weight of the other value) instead of the number of values inside. The position passed along is the weighted average of the values' positions, the center of mass.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::new();
+    ///
+    /// bht.push_weighted(&[-1.0, 0.0], 1.0);
+    /// bht.push_weighted(&[1.0, 0.0], 2.0);
+    /// bht.push_weighted(&[1.0, 2.0], 2.0);
+    ///
+    /// // Accumulating the total weight and the weighted sum of positions of the other values.
+    /// let mut ans_weight_and_moment = (0.0, [0.0; 2]);
+    ///
+    /// let is_super_fn = |_: &[f64; 2], _: &[f64; 2], _: f64| -> bool { true }; // Every node is "far" enough.
+    /// let calc_fn = |_: &[f64; 2], other_vc: &[f64; 2], weight: f64, ans: &mut (f64, [f64; 2])| {
+    ///     ans.0 += weight;
+    ///     for d in 0..2 {
+    ///         ans.1[d] += weight * other_vc[d];
+    ///     }
+    /// };
+    ///
+    /// bht.calc_weighted_force_on_value(0, &is_super_fn, &calc_fn, &mut ans_weight_and_moment);
+    ///
+    /// assert_eq!(ans_weight_and_moment, (4.0, [4.0, 4.0]));
+    /// ```
+    ///
+    pub fn calc_weighted_force_on_value<T>(
+        &self,
+        value_i: usize,
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], &[F; D], F, &mut T),
+        write_to_value: &mut T,
+    ) -> bool {
+        if value_i >= self.vs.len() {
+            return false;
+        }
+
+        self.calc_interactions_on_value(
+            value_i,
+            &is_super_node,
+            |curr_v_ref, other, write_to| {
+                calc_fn(
+                    curr_v_ref,
+                    other.get_vc(),
+                    other.get_values_weight_inside(),
+                    write_to,
+                )
+            },
+            write_to_value,
+        );
+        true
+    }
+
+    /// Calculate force or custom relationships between selected super nodes on a specific target value (body), taking the values' signed charges into account.
+    ///
+    /// Since positive and negative charges can cancel each other out, the calculation closure receives the positive and the negative aggregates separately:
+    /// - the target value,
+    /// - the positive-charge-weighted center and the total positive charge of a super node (or the other value),
+    /// - the negative-charge-weighted center and the total negative charge (a number less than or equal to zero),
+    /// - the answer's mutable reference.
+    ///
+    /// If one side contains no charge, its total is zero, and its center should be ignored.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::new();
+    ///
+    /// bht.push_charged(&[0.0, 0.0], 1.0);
+    /// bht.push_charged(&[1.0, 0.0], 2.0);
+    /// bht.push_charged(&[3.0, 0.0], 2.0);
+    /// bht.push_charged(&[0.0, 2.0], -1.0);
+    ///
+    /// // Accumulating the total charges and the charge-weighted sums of positions of the other values.
+    /// let mut ans = ([0.0; 2], 0.0, [0.0; 2], 0.0);
+    ///
+    /// let is_super_fn = |_: &[f64; 2], _: &[f64; 2], _: f64| -> bool { true }; // Every node is "far" enough.
+    /// let calc_fn = |_: &[f64; 2],
+    ///                pos_vc: &[f64; 2],
+    ///                pos_q: f64,
+    ///                neg_vc: &[f64; 2],
+    ///                neg_q: f64,
+    ///                ans: &mut ([f64; 2], f64, [f64; 2], f64)| {
+    ///     for d in 0..2 {
+    ///         ans.0[d] += pos_q * pos_vc[d];
+    ///         ans.2[d] += neg_q * neg_vc[d];
+    ///     }
+    ///     ans.1 += pos_q;
+    ///     ans.3 += neg_q;
+    /// };
+    ///
+    /// bht.calc_charged_force_on_value(0, &is_super_fn, &calc_fn, &mut ans);
+    ///
+    /// assert_eq!(ans, ([8.0, 0.0], 4.0, [0.0, -2.0], -1.0));
+    /// ```
+    ///
+    pub fn calc_charged_force_on_value<T>(
+        &self,
+        value_i: usize,
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], &[F; D], F, &[F; D], F, &mut T),
+        write_to_value: &mut T,
+    ) -> bool {
+        if value_i >= self.vs.len() {
+            return false;
+        }
+
+        self.calc_interactions_on_value(
+            value_i,
+            &is_super_node,
+            |curr_v_ref, other, write_to| {
+                let (pos_vc, pos_q, neg_vc, neg_q) = other.get_charges();
+                calc_fn(curr_v_ref, pos_vc, pos_q, neg_vc, neg_q, write_to)
+            },
+            write_to_value,
+        );
+        true
+    }
+
+    /// Calculate force or custom relationships between selected super nodes on a specific target value (body) with the super nodes' second-order moments.
+    ///
+    /// This method requires the `quadrupole` feature. In addition to the arguments of [BarnesHutTree::calc_weighted_force_on_value]'s closure, the calculation closure receives the second central moment of the super node, `sum(w * (v - vc) * (v - vc)^T)`, before the answer's mutable reference. Since the value center is the center of mass, the dipole term vanishes, and kernels can apply the second-order (quadrupole) correction with this tensor. For example, the traceless quadrupole tensor in three dimensions is `3 * M - trace(M) * I`.
+    ///
+    /// A single value (body) has a moment of zeros.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> =
+    ///     BHTree::with_bounding_and_values(&[0.0, 0.0], 4.0, &[[-3.0, -3.0], [1.0, 3.0], [3.0, 1.0]]);
+    ///
+    /// let mut ans_moment = [[0.0; 2]; 2];
+    ///
+    /// let is_super_fn = |_: &[f64; 2], _: &[f64; 2], _: f64| -> bool { true }; // Every node is "far" enough.
+    /// let calc_fn = |_: &[f64; 2], _: &[f64; 2], _: f64, moment: &[[f64; 2]; 2], ans: &mut [[f64; 2]; 2]| {
+    ///     *ans = *moment;
+    /// };
+    ///
+    /// bht.calc_force_on_value_with_quadrupole(0, &is_super_fn, &calc_fn, &mut ans_moment);
+    ///
+    /// // The super node holding the other two values with the value center `[2.0, 2.0]`.
+    /// assert_eq!(ans_moment, [[2.0, -2.0], [-2.0, 2.0]]);
+    /// ```
+    ///
+    #[cfg(feature = "quadrupole")]
+    pub fn calc_force_on_value_with_quadrupole<T>(
+        &self,
+        value_i: usize,
+        is_super_node: impl OpeningCriterion<D, F>,
+        calc_fn: impl Fn(&[F; D], &[F; D], F, &[[F; D]; D], &mut T),
+        write_to_value: &mut T,
+    ) -> bool {
+        if value_i >= self.vs.len() {
+            return false;
+        }
+
+        self.calc_interactions_on_value(
+            value_i,
+            &is_super_node,
+            |curr_v_ref, other, write_to| {
+                calc_fn(
+                    curr_v_ref,
+                    other.get_vc(),
+                    other.get_values_weight_inside(),
+                    &other.get_moment(),
+                    write_to,
+                )
+            },
+            write_to_value,
+        );
+        true
+    }
+}
+
+impl<const D: Udim, F: Fnum> Default for BarnesHutTree<D, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Utilities
+///
+/// These methods are about getting, pushing, removing, and updating values (bodies) inside the tree.
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    /// Get a reference of the stored value's coordinates.
+    ///
+    /// ## Return
+    ///
+    /// This method returns a reference of the current value (body)'s coordinates if the index is within-range.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
+    ///
+    /// assert_eq!(bht.get(0), Some(&[-1.0,1.0]));
+    /// assert_eq!(bht.get(2), None);
+    /// ```
+    ///
+    pub fn get(&self, value_i: usize) -> Option<&[F; D]> {
+        if value_i >= self.vs.len() {
+            return None;
+        }
+        Some(&self.vs[value_i].0.data)
+    }
+
+    /// Push a value into the tree.
+    ///
+    /// ## Return
+    ///
+    /// This method will return the value's corresponding value-index `usize` in the tree.
+    ///
+    /// This design is mainly for easier mapping of values in case we want to connect the values with other format of keys, for example, `String` or `usize` that is not filling an entire range from `0..len`.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
+    ///
+    /// bht.push(&[-1.0,1.0]);
+    /// let idx = bht.push(&[1.0,1.0]);
+    ///
+    /// assert_eq!(bht.get(0), Some(&[-1.0,1.0]));
+    /// assert_eq!(bht.get(idx), Some(&[1.0,1.0]));
+    /// ```
+    ///
+    pub fn push(&mut self, value_ref: &[F; D]) -> usize {
+        self.push_weighted(value_ref, F::ONE)
+    }
+
+    /// Push a value with a weight (mass) into the tree.
+    ///
+    /// The weight should be finite and greater than zero. Values pushed by [BarnesHutTree::push] have a weight of one.
+    ///
+    /// ## Return
+    ///
+    /// This method will return the value's corresponding value-index `usize` in the tree.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::new();
+    ///
+    /// bht.push(&[-1.0,1.0]);
+    /// let idx = bht.push_weighted(&[1.0,1.0], 3.0);
+    ///
+    /// assert_eq!(bht.get_weight(0), Some(1.0));
+    /// assert_eq!(bht.get_weight(idx), Some(3.0));
+    /// ```
+    ///
+    pub fn push_weighted(&mut self, value_ref: &[F; D], weight: F) -> usize {
+        self.push_weighted_and_charged(value_ref, weight, F::ZERO)
+    }
+
+    /// Push a value with a signed charge into the tree.
+    ///
+    /// The charge should be finite. The value's weight (mass) is one. Values pushed by other methods have a charge of zero.
+    ///
+    /// ## Return
+    ///
+    /// This method will return the value's corresponding value-index `usize` in the tree.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::new();
+    ///
+    /// bht.push(&[-1.0,1.0]);
+    /// let idx = bht.push_charged(&[1.0,1.0], -2.0);
+    ///
+    /// assert_eq!(bht.get_charge(0), Some(0.0));
+    /// assert_eq!(bht.get_charge(idx), Some(-2.0));
+    /// ```
+    ///
+    pub fn push_charged(&mut self, value_ref: &[F; D], charge: F) -> usize {
+        self.push_weighted_and_charged(value_ref, F::ONE, charge)
+    }
+
+    /// Push a value with both a weight (mass) and a signed charge into the tree.
+    ///
+    /// The weight should be finite and greater than zero, and the charge should be finite. The value is inserted once, so there is no need to push it with [BarnesHutTree::push_weighted] and then [BarnesHutTree::update_charge].
+    ///
+    /// ## Return
+    ///
+    /// This method will return the value's corresponding value-index `usize` in the tree.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::new();
+    ///
+    /// bht.push(&[-1.0,1.0]);
+    /// let idx = bht.push_weighted_and_charged(&[1.0,1.0], 3.0, -2.0);
+    ///
+    /// assert_eq!(bht.get_weight(idx), Some(3.0));
+    /// assert_eq!(bht.get_charge(idx), Some(-2.0));
+    /// ```
+    ///
+    pub fn push_weighted_and_charged(&mut self, value_ref: &[F; D], weight: F, charge: F) -> usize {
+        assert!(
+            weight.is_finite() && weight > F::ZERO,
+            "The weight should be finite and greater than zero."
+        );
+        assert!(charge.is_finite(), "The charge should be finite.");
+        let value_i = self.vs.len();
+        self.vs
+            .push(Box::new((ColVec::new_with_arr(value_ref), None)));
+        self.ws.push(weight);
+        self.qs.push(charge);
+
+        self.add(value_i);
+        value_i
+    }
+
+    /// Get the signed charge of a stored value.
+    ///
+    /// ## Return
+    ///
+    /// This method returns the charge of the current value (body) if the index is within-range.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::new();
+    ///
+    /// bht.push_charged(&[-1.0,1.0], 0.5);
+    ///
+    /// assert_eq!(bht.get_charge(0), Some(0.5));
+    /// assert_eq!(bht.get_charge(1), None);
+    /// ```
+    ///
+    pub fn get_charge(&self, value_i: usize) -> Option<F> {
+        self.qs.get(value_i).copied()
+    }
+
+    /// Update the signed charge of a value.
+    ///
+    /// The charge should be finite.
+    ///
+    /// ## Return
+    ///
+    /// This method returns a boolean indicating whether the update is successful. The update will usually be successful if the value index is pointing to a valid value.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::new();
+    ///
+    /// bht.push(&[-1.0,1.0]);
+    /// bht.push(&[1.0,1.0]);
+    ///
+    /// assert!(bht.update_charge(1, -1.0));
+    /// assert!(!bht.update_charge(2, -1.0));
+    ///
+    /// assert_eq!(bht.get_charge(1), Some(-1.0));
+    /// ```
+    ///
+    pub fn update_charge(&mut self, value_i: usize, charge: F) -> bool {
+        if value_i >= self.vs.len() {
+            return false;
+        }
+        assert!(charge.is_finite(), "The charge should be finite.");
+        self.sub(value_i);
+        self.qs[value_i] = charge;
+        self.add(value_i);
+        true
+    }
+
+    /// Get the weight (mass) of a stored value.
+    ///
+    /// ## Return
+    ///
+    /// This method returns the weight of the current value (body) if the index is within-range.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
+    ///
+    /// assert_eq!(bht.get_weight(0), Some(1.0));
+    /// assert_eq!(bht.get_weight(2), None);
+    /// ```
+    ///
+    pub fn get_weight(&self, value_i: usize) -> Option<F> {
+        self.ws.get(value_i).copied()
+    }
+
+    /// Update the weight (mass) of a value.
+    ///
+    /// The weight should be finite and greater than zero.
+    ///
+    /// ## Return
+    ///
+    /// This method returns a boolean indicating whether the update is successful. The update will usually be successful if the value index is pointing to a valid value.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
+    ///
+    /// bht.push(&[-1.0,1.0]);
+    /// bht.push(&[1.0,1.0]);
+    ///
+    /// assert!(bht.update_weight(1, 4.0));
+    /// assert!(!bht.update_weight(2, 4.0));
+    ///
+    /// assert_eq!(bht.get_weight(1), Some(4.0));
+    /// ```
+    ///
+    pub fn update_weight(&mut self, value_i: usize, weight: F) -> bool {
+        if value_i >= self.vs.len() {
+            return false;
+        }
+        assert!(
+            weight.is_finite() && weight > F::ZERO,
+            "The weight should be finite and greater than zero."
+        );
+        self.sub(value_i);
+        self.ws[value_i] = weight;
+        self.add(value_i);
+        true
+    }
+
+    /// Update the coordinates of a value.
+    ///
+    /// ## Return
+    ///
+    /// This method returns a boolean indicating whether the update is successful. The update will usually be successful if the value index is pointing to a valid value.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
+    ///
+    /// bht.push(&[-0.5,1.0]);
+    /// let idx = bht.push(&[1.0,1.0]);
+    ///
+    /// bht.update(0, &[-1.0,1.0]);
+    ///
+    /// assert_eq!(bht.get(0), Some(&[-1.0,1.0]));
+    /// assert_eq!(bht.get(idx), Some(&[1.0,1.0]));
+    /// ```
+    ///
+    pub fn update(&mut self, value_i: usize, value_ref: &[F; D]) -> bool {
+        let len = self.vs.len();
+        if value_i >= len {
+            return false;
+        }
+        self.sub(value_i);
+        debug_assert!(
+            self.vs[value_i].1.is_none(),
+            "The to_leaf index should be `None`."
+        );
+        self.vs[value_i].0.clone_from_arr_ref(value_ref);
+        self.add(value_i);
+        true
+    }
+
+    /// Remove a value (body) from the tree.
+    ///
+    /// ## Return
+    ///
+    /// Since the underlying structure uses `vec` to store nodes, if a value that is not the last one in the `vec` needs to be removed, the last value from the `vec` will replace its position. The method returns an option of an index `usize` to indicate which value was moved to that position to replace the removed one (always the previous last one). If the to-remove value is the last one, or the index is out-of-range, the method will return a `None`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
+    ///
+    /// bht.push(&[-0.5,1.0]);
+    /// bht.push(&[1.0,1.0]);
+    ///
+    /// let old_i = bht.remove(0);
+    ///
+    /// assert_eq!(old_i, Some(1));
+    /// assert_eq!(bht.get(0).unwrap(), &[1.0,1.0]);
+    /// assert_eq!(bht.remove(1), None);
+    /// ```
+    ///
+    pub fn remove(&mut self, value_i: usize) -> Option<usize> {
+        let last_i = self.vs.len() - 1;
+        if value_i > last_i {
+            return None;
+        }
+        self.sub(value_i);
+        self.ws.swap_remove(value_i);
+        self.qs.swap_remove(value_i);
+        let last_v_opt = self.vs.pop().expect("Should have a last");
+        if value_i < last_i {
+            if let Some((leaf_i, in_leaf_i)) = last_v_opt.1 {
+                #[cfg(not(feature = "unchecked"))]
+                {
+                    *self
+                        .leaf_vec
+                        .get_mut(leaf_i)
+                        .expect("To update the leaf node's value pointing index to the new value location; The leaf should be valid")
+                        .vs
+                        .get_mut(in_leaf_i)
+                        .expect("To update the leaf node's value pointing index to the new value location; The in-leaf position should be valid") = value_i;
+                }
+
+                #[cfg(feature = "unchecked")]
+                {
+                    unsafe {
+                        *self
+                            .leaf_vec
+                            .get_unchecked_mut(leaf_i)
+                            .vs
+                            .get_unchecked_mut(in_leaf_i) = value_i;
+                    }
+                }
+            }
+            self.vs[value_i] = last_v_opt;
+            Some(last_i)
+        } else {
+            None
+        }
+    }
+
+    /// Rebuild the tree with the minimal hypercube enclosing all the current values.
+    ///
+    /// The root only expands when a value moves out of it, so after the values have moved inward, for example, during a converging force simulation, the tree could stay rooted at a huge hypercube with a long chain of internal nodes down to the values. This method rebuilds all the nodes in bulk to shrink the root. The values' indices, weights, and charges are kept.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::from_values(&[[-1.0,1.0],[1.0,1.0],[1000.0,1000.0]]);
+    ///
+    /// bht.update(2, &[1.0,-1.0]);
+    /// assert_eq!(bht.get_total_nodes_num(), 13);
+    ///
+    /// bht.recompute_bounds();
+    /// assert_eq!(bht.get_total_nodes_num(), 4);
+    /// assert_eq!(bht.get(2), Some(&[1.0,-1.0]));
+    /// ```
+    ///
+    pub fn recompute_bounds(&mut self) {
+        if let Some(bb) = BoundBox::new_enclosing(self.vs.iter().map(|v| &v.0.data), self.br_limit)
+        {
+            self.rebuild(bb);
+        }
+    }
+
+    /// Shrink the root by dropping the root internal nodes with only one child.
+    ///
+    /// Unlike [BarnesHutTree::recompute_bounds], this method does not rebuild the tree. It only drops the chain of one-child internal nodes from the root down to the first node with more than one child (or a leaf), and tightens the bounding hypercube to that node's. The root still expands when a value moves out of it.
+    ///
+    /// ## Return
+    ///
+    /// This method returns whether the root has been shrunk.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],4.0, 100);
+    ///
+    /// bht.push(&[1.0,3.0]);
+    /// bht.push(&[3.0,1.0]);
+    /// bht.push(&[-3.0,-3.0]);
+    /// assert_eq!(bht.get_total_nodes_num(), 5);
+    ///
+    /// bht.remove(2);
+    /// assert_eq!(bht.get_total_nodes_num(), 4);
+    ///
+    /// assert!(bht.shrink_root());
+    /// assert_eq!(bht.get_total_nodes_num(), 3);
+    /// assert!(!bht.shrink_root());
+    /// ```
+    ///
+    pub fn shrink_root(&mut self) -> bool {
+        self.drop_one_child_roots()
+    }
+
+    /// Get the total number of nodes
+    ///
+    /// ## Return
+    ///
+    /// This method returns the total number of nodes in the tree.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0,0.0],2.0, 100);
+    ///
+    /// assert_eq!(bht.get_total_nodes_num(), 0);
+    ///
+    /// bht.push(&[-1.0,1.0]);
+    /// assert_eq!(bht.get_total_nodes_num(), 1);
+    ///
+    /// bht.push(&[1.0,1.0]);
+    /// assert_eq!(bht.get_total_nodes_num(), 3);
+    ///
+    /// bht.remove(0);
+    /// assert_eq!(bht.get_total_nodes_num(), 1);
+    ///
+    /// bht.remove(0);
+    /// assert_eq!(bht.get_total_nodes_num(), 0);
     /// ```
     ///
     #[inline]
     pub fn get_total_nodes_num(&self) -> usize {
         self.internal_vec.len() + self.leaf_vec.len()
     }
+
+    /// Get the read-only cursor of the root node.
+    ///
+    /// ## Return
+    ///
+    /// This method returns `None` if the tree has no values.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],4.0, &[[-1.0,1.0],[1.0,1.0],[1.0,3.0]]);
+    ///
+    /// let root = bht.root().unwrap();
+    /// assert_eq!(root.bounding_center(), &[0.0, 0.0]);
+    /// assert_eq!(root.half_width(), 4.0);
+    /// assert_eq!(root.count(), 3);
+    /// assert_eq!(root.parent(), None);
+    ///
+    /// // The leaves hold all the values.
+    /// let mut ans_indices: Vec<usize> = root
+    ///     .depth_first()
+    ///     .filter_map(|node| node.values())
+    ///     .flatten()
+    ///     .cloned()
+    ///     .collect();
+    /// ans_indices.sort();
+    /// assert_eq!(ans_indices, vec![0, 1, 2]);
+    ///
+    /// for child in root.children() {
+    ///     assert_eq!(child.parent(), Some(root));
+    ///     assert_eq!(child.half_width(), 2.0);
+    /// }
+    ///
+    /// let bht: BHTree<2> = BHTree::new();
+    /// assert!(bht.root().is_none());
+    /// ```
+    ///
+    pub fn root(&self) -> Option<NodeRef<'_, D, F>> {
+        self.root.map(|node| NodeRef::new(self, node))
+    }
+}
+
+/// # Queries
+///
+/// These methods are about finding values (bodies) by their positions, using the bounding hypercubes of the nodes to skip the parts of the tree that cannot match.
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    /// Find the `k` nearest values to a point.
+    ///
+    /// The point does not need to be a value in the tree.
+    ///
+    /// ## Return
+    ///
+    /// This method returns the indices of the values and their distances to the point, sorted from the nearest. Values at the same distance are sorted by their indices. Fewer than `k` values are returned if the tree does not have enough values.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[0.0,0.0],[3.0,4.0],[1.0,0.0],[-2.0,0.0]]);
+    ///
+    /// assert_eq!(bht.knn(&[0.0,0.0], 3), vec![(0, 0.0), (2, 1.0), (3, 2.0)]);
+    /// assert_eq!(bht.knn(&[0.0,0.0], 5).len(), 4);
+    /// ```
+    ///
+    pub fn knn(&self, point: &[F; D], k: usize) -> Vec<(usize, F)> {
+        self.find_knn(point, k, None)
+    }
+
+    /// Find the `k` nearest other values to a value in the tree.
+    ///
+    /// The target value itself is not counted, while other values at the same position are.
+    ///
+    /// ## Return
+    ///
+    /// This method returns the same as [BarnesHutTree::knn], or `None` if the target index is out of range.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[0.0,0.0],[3.0,4.0],[1.0,0.0],[-2.0,0.0]]);
+    ///
+    /// assert_eq!(bht.knn_of_value(0, 2), Some(vec![(2, 1.0), (3, 2.0)]));
+    /// assert_eq!(bht.knn_of_value(4, 2), None);
+    /// ```
+    ///
+    pub fn knn_of_value(&self, value_i: usize, k: usize) -> Option<Vec<(usize, F)>> {
+        let point = *self.get(value_i)?;
+        Some(self.find_knn(&point, k, Some(value_i)))
+    }
+
+    /// Visit every value within the distance `r` (inclusive) to a point.
+    ///
+    /// The point does not need to be a value in the tree. `visit_fn` is called once with the index of each value inside the ball, in no particular order. Nothing is visited if `r` is negative.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[0.0,0.0],[3.0,4.0],[1.0,0.0],[-2.0,0.0]]);
+    ///
+    /// let mut ans_indices = Vec::new();
+    /// bht.query_ball(&[0.0,0.0], 2.0, &mut |value_i| ans_indices.push(value_i));
+    /// ans_indices.sort();
+    /// assert_eq!(ans_indices, vec![0, 2, 3]);
+    /// ```
+    ///
+    pub fn query_ball(&self, point: &[F; D], r: F, visit_fn: &mut impl FnMut(usize)) {
+        self.visit_ball(point, r, None, visit_fn);
+    }
+
+    /// Get the indices of all the values within the distance `r` (inclusive) to a point.
+    ///
+    /// This method is the same as [BarnesHutTree::query_ball], collecting the indices.
+    ///
+    /// ## Return
+    ///
+    /// This method returns the indices sorted in ascending order.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[0.0,0.0],[3.0,4.0],[1.0,0.0],[-2.0,0.0]]);
+    ///
+    /// assert_eq!(bht.query_ball_vec(&[0.0,0.0], 1.0), vec![0, 2]);
+    /// assert_eq!(bht.query_ball_vec(&[0.0,0.0], 5.0), vec![0, 1, 2, 3]);
+    /// assert!(bht.query_ball_vec(&[0.0,0.0], -1.0).is_empty());
+    /// ```
+    ///
+    pub fn query_ball_vec(&self, point: &[F; D], r: F) -> Vec<usize> {
+        let mut ans = Vec::new();
+        self.visit_ball(point, r, None, &mut |value_i| ans.push(value_i));
+        ans.sort_unstable();
+        ans
+    }
+
+    /// Visit every other value within the distance `r` (inclusive) to a value in the tree.
+    ///
+    /// This method is the same as [BarnesHutTree::query_ball] around the target value, but the target value itself is not visited.
+    ///
+    /// ## Return
+    ///
+    /// This method returns `false` without visiting anything if the target index is out of range.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[0.0,0.0],[3.0,4.0],[1.0,0.0],[-2.0,0.0]]);
+    ///
+    /// let mut ans_indices = Vec::new();
+    /// assert!(bht.query_ball_of_value(2, 3.0, &mut |value_i| ans_indices.push(value_i)));
+    /// ans_indices.sort();
+    /// assert_eq!(ans_indices, vec![0, 3]);
+    ///
+    /// assert!(!bht.query_ball_of_value(4, 3.0, &mut |value_i| ans_indices.push(value_i)));
+    /// ```
+    ///
+    pub fn query_ball_of_value(
+        &self,
+        value_i: usize,
+        r: F,
+        visit_fn: &mut impl FnMut(usize),
+    ) -> bool {
+        let Some(point) = self.get(value_i).cloned() else {
+            return false;
+        };
+        self.visit_ball(&point, r, Some(value_i), visit_fn);
+        true
+    }
+
+    /// Visit every value inside the axis-aligned box from `min` to `max` (both inclusive).
+    ///
+    /// `visit_fn` is called once with the index of each value inside the box, in no particular order. The nodes inside the box are visited as a whole without checking their values one by one. Nothing is visited if `min` is greater than `max` on any dimension.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[0.0,0.0],[3.0,4.0],[1.0,0.0],[-2.0,0.0]]);
+    ///
+    /// let mut ans_indices = Vec::new();
+    /// bht.query_aabb(&[-2.0,0.0], &[1.0,1.0], &mut |value_i| ans_indices.push(value_i));
+    /// ans_indices.sort();
+    /// assert_eq!(ans_indices, vec![0, 2, 3]);
+    /// ```
+    ///
+    pub fn query_aabb(&self, min: &[F; D], max: &[F; D], visit_fn: &mut impl FnMut(usize)) {
+        self.visit_aabb(min, max, visit_fn);
+    }
+
+    /// Get the indices of all the values inside the axis-aligned box from `min` to `max` (both inclusive).
+    ///
+    /// This method is the same as [BarnesHutTree::query_aabb], collecting the indices.
+    ///
+    /// ## Return
+    ///
+    /// This method returns the indices sorted in ascending order.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[0.0,0.0],[3.0,4.0],[1.0,0.0],[-2.0,0.0]]);
+    ///
+    /// assert_eq!(bht.query_aabb_vec(&[0.0,-1.0], &[5.0,5.0]), vec![0, 1, 2]);
+    /// assert!(bht.query_aabb_vec(&[1.0,1.0], &[-1.0,-1.0]).is_empty());
+    /// ```
+    ///
+    pub fn query_aabb_vec(&self, min: &[F; D], max: &[F; D]) -> Vec<usize> {
+        let mut ans = Vec::new();
+        self.visit_aabb(min, max, &mut |value_i| ans.push(value_i));
+        ans.sort_unstable();
+        ans
+    }
+
+    /// Visit every pair of values closer than the distance `r` (exclusive).
+    ///
+    /// The pairs of nodes are walked from the root as in [BarnesHutTree::calc_forces_dual_tree], and two nodes at least `r` away from each other are skipped as a whole. `visit_fn` is called once for each pair with the smaller index, the larger index, and the distance between the two values, in no particular order. Nothing is visited if `r` is not positive.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use zhifeng_impl_barnes_hut_tree as zbht;
+    ///
+    /// use zbht::BarnesHutTree as BHTree;
+    ///
+    /// let bht: BHTree<2> = BHTree::from_values(&[[0.0,0.0],[3.0,4.0],[1.0,0.0],[-2.0,0.0]]);
+    ///
+    /// let mut ans_pairs = Vec::new();
+    /// bht.for_each_pair_within(2.5, |value_a_i, value_b_i, dis| ans_pairs.push((value_a_i, value_b_i, dis)));
+    /// ans_pairs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    /// assert_eq!(ans_pairs, vec![(0, 2, 1.0), (0, 3, 2.0)]);
+    /// ```
+    ///
+    pub fn for_each_pair_within(&self, r: F, mut visit_fn: impl FnMut(usize, usize, F)) {
+        self.visit_pairs_within(r, &mut visit_fn);
+    }
 }
 
 pub mod utils;
 
-#[cfg(any(feature = "serialize"))]
+#[cfg(feature = "serialize")]
 mod serialize;
-#[cfg(any(feature = "serialize"))]
+#[cfg(feature = "serialize")]
 pub use serialize::BarnesHutTreeSer;
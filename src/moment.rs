@@ -0,0 +1,161 @@
+use crate::{colvec::ColVec, Fnum, Udim};
+
+/// # The second central moment of a node
+///
+/// The tensor is the weighted sum of the outer products of the values' offsets to the node's value center, `sum(w * (v - vc) * (v - vc)^T)`.
+///
+/// Since the value center moves whenever a value is added or removed, the tensor is updated with the previous and the next value centers, in the same way as Welford's online covariance algorithm. This avoids the cancellation problem of keeping the raw second moment about the origin.
+#[derive(Debug, PartialEq)]
+pub struct Moment<const D: Udim, F: Fnum> {
+    pub(crate) data: [[F; D]; D],
+}
+
+impl<const D: Udim, F: Fnum> Moment<D, F> {
+    #[inline]
+    pub fn new_zeros() -> Self {
+        Self {
+            data: [[F::ZERO; D]; D],
+        }
+    }
+
+    /// Update the tensor after a value `v` with weight `w` was added, moving the value center from `prev_vc` to `next_vc`.
+    #[inline]
+    pub fn update_with_one_new_data(
+        &mut self,
+        prev_vc: &ColVec<D, F>,
+        next_vc: &ColVec<D, F>,
+        v: &ColVec<D, F>,
+        w: F,
+    ) {
+        for i in 0..D {
+            let prev_diff = v.data[i] - prev_vc.data[i];
+            for j in 0..D {
+                self.data[i][j] += w * prev_diff * (v.data[j] - next_vc.data[j]);
+                assert!(
+                    self.data[i][j].is_finite(),
+                    "A numeric error occurred when calculating the new moment after node adding..."
+                );
+            }
+        }
+    }
+
+    /// Update the tensor after a value `v` with weight `w` was removed, moving the value center from `prev_vc` to `next_vc`.
+    ///
+    /// If no values remain, the tensor is reset to zeros.
+    #[inline]
+    pub fn update_with_one_data_removal(
+        &mut self,
+        prev_vc: &ColVec<D, F>,
+        next_vc: &ColVec<D, F>,
+        v: &ColVec<D, F>,
+        w: F,
+        next_count: usize,
+    ) {
+        if next_count == 0 {
+            self.data = [[F::ZERO; D]; D];
+            return;
+        }
+        for i in 0..D {
+            let next_diff = v.data[i] - next_vc.data[i];
+            for j in 0..D {
+                self.data[i][j] -= w * next_diff * (v.data[j] - prev_vc.data[j]);
+                assert!(
+                    self.data[i][j].is_finite(),
+                    "A numeric error occurred when calculating the new moment after node removal..."
+                );
+            }
+        }
+    }
+}
+
+impl<const D: Udim, F: Fnum> Moment<D, F> {
+    /// Calculate the trace of the tensor, which is the weighted sum of the squared distances from the values to the node's value center.
+    #[inline]
+    pub fn calc_trace(&self) -> F {
+        let mut ans = F::ZERO;
+        for (d, row) in self.data.iter().enumerate() {
+            ans += row[d];
+        }
+        ans
+    }
+}
+
+impl<const D: Udim, F: Fnum> Clone for Moment<D, F> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { data: self.data }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn calc_direct_moment<const D: Udim>(vs: &[([f64; D], f64)]) -> [[f64; D]; D] {
+        let mut total_w = 0.0;
+        let mut vc = [0.0; D];
+        for (v, w) in vs {
+            total_w += w;
+            for d in 0..D {
+                vc[d] += w * v[d];
+            }
+        }
+        for vc_d in vc.iter_mut() {
+            *vc_d /= total_w;
+        }
+        let mut ans = [[0.0; D]; D];
+        for (v, w) in vs {
+            for i in 0..D {
+                for j in 0..D {
+                    ans[i][j] += w * (v[i] - vc[i]) * (v[j] - vc[j]);
+                }
+            }
+        }
+        ans
+    }
+
+    fn assert_moment_close<const D: Udim>(got: &[[f64; D]; D], expected: &[[f64; D]; D]) {
+        for i in 0..D {
+            for j in 0..D {
+                assert!(
+                    (got[i][j] - expected[i][j]).abs() < 1e-9,
+                    "     Got:{:?}\nExpected:{:?}",
+                    got,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn check_add_and_sub_moment() {
+        let vs: Vec<([f64; 3], f64)> = vec![
+            ([1.0, 2.0, 3.0], 1.0),
+            ([-1.0, 0.5, 2.0], 2.0),
+            ([4.0, -3.0, 1.0], 0.5),
+            ([0.0, 1.0, -2.0], 3.0),
+        ];
+
+        let mut vc: ColVec<3, f64> = ColVec::new_zeros();
+        let mut total_w = 0.0;
+        let mut moment: Moment<3, f64> = Moment::new_zeros();
+        for (v, w) in vs.iter() {
+            let v = ColVec::new_with_arr(v);
+            let prev_vc = vc.clone();
+            vc.update_online_average_with_one_new_data(total_w, &v.data, *w);
+            total_w += w;
+            moment.update_with_one_new_data(&prev_vc, &vc, &v, *w);
+        }
+        assert_moment_close(&moment.data, &calc_direct_moment(&vs));
+
+        for count in (1..vs.len()).rev() {
+            let (v, w) = vs[count];
+            let v = ColVec::new_with_arr(&v);
+            let prev_vc = vc.clone();
+            vc.update_online_average_with_one_data_removal(count + 1, total_w, &v.data, w);
+            total_w -= w;
+            moment.update_with_one_data_removal(&prev_vc, &vc, &v, w, count);
+            assert_moment_close(&moment.data, &calc_direct_moment(&vs[..count]));
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::{collections::VecDeque, fmt, ptr};
+
+use crate::{
+    imple::get_ref_from_arr_ref,
+    nodes::{Internal, Leaf, NodeIndex},
+    BarnesHutTree, Fnum, NodeSummary, Udim,
+};
+
+/// # A read-only cursor on a node of the tree
+///
+/// A node is either an internal node with up to two to the power of `D` children, or a leaf holding the values (bodies) directly. The cursor borrows the tree, so the tree cannot be changed while any cursor is alive.
+///
+/// The cursor of the root is returned by [BarnesHutTree::root]. From there, we can move to the children or back to the parent, read the node's bounding hypercube and aggregates, and walk all the nodes below with [NodeRef::depth_first] or [NodeRef::breadth_first].
+pub struct NodeRef<'o, const D: Udim, F: Fnum> {
+    bht: &'o BarnesHutTree<D, F>,
+    node: NodeIndex,
+}
+
+impl<const D: Udim, F: Fnum> Clone for NodeRef<'_, D, F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<const D: Udim, F: Fnum> Copy for NodeRef<'_, D, F> {}
+
+/// Two cursors are equal if they are on the same node of the same tree.
+impl<const D: Udim, F: Fnum> PartialEq for NodeRef<'_, D, F> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.bht, other.bht) && self.node == other.node
+    }
+}
+
+impl<const D: Udim, F: Fnum> Eq for NodeRef<'_, D, F> {}
+
+/// A cursor is shown by the kind and the index of its node, without the whole tree.
+impl<const D: Udim, F: Fnum> fmt::Debug for NodeRef<'_, D, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeRef").field("node", &self.node).finish()
+    }
+}
+
+/// # The node a cursor is on
+enum NodeKind<'o, const D: Udim, F: Fnum> {
+    Internal(&'o Internal<D, F>),
+    Leaf(&'o Leaf<D, F>),
+}
+
+impl<'o, const D: Udim, F: Fnum> NodeRef<'o, D, F> {
+    #[inline]
+    pub(crate) fn new(bht: &'o BarnesHutTree<D, F>, node: NodeIndex) -> Self {
+        Self { bht, node }
+    }
+
+    #[inline]
+    fn get_kind(&self) -> NodeKind<'o, D, F> {
+        match self.node {
+            NodeIndex::In(internal_i) => NodeKind::Internal(get_ref_from_arr_ref(
+                &self.bht.internal_vec,
+                internal_i,
+                "A cursor should always be on an existing internal node",
+            )),
+            NodeIndex::Le(leaf_i) => NodeKind::Leaf(get_ref_from_arr_ref(
+                &self.bht.leaf_vec,
+                leaf_i,
+                "A cursor should always be on an existing leaf node",
+            )),
+        }
+    }
+
+    /// Check whether the node is a leaf holding values directly.
+    pub fn is_leaf(&self) -> bool {
+        matches!(self.node, NodeIndex::Le(_))
+    }
+
+    /// Get the cursors of the node's children, in the order of their directions. A leaf has no children.
+    pub fn children(&self) -> impl Iterator<Item = NodeRef<'o, D, F>> + 'o {
+        let bht = self.bht;
+        let nexts: &'o [Option<NodeIndex>] = match self.get_kind() {
+            NodeKind::Internal(internal_ref) => &internal_ref.nexts,
+            NodeKind::Leaf(_) => &[],
+        };
+        nexts
+            .iter()
+            .flatten()
+            .map(move |node| NodeRef::new(bht, *node))
+    }
+
+    /// Get the cursor of the node's parent, or `None` for the root.
+    pub fn parent(&self) -> Option<NodeRef<'o, D, F>> {
+        let parent = match self.get_kind() {
+            NodeKind::Internal(internal_ref) => internal_ref.parent,
+            NodeKind::Leaf(leaf_ref) => leaf_ref.parent,
+        };
+        parent.map(|(parent_i, _)| NodeRef::new(self.bht, NodeIndex::In(parent_i)))
+    }
+
+    /// Get the center of the node's bounding hypercube, `bc` for short.
+    pub fn bounding_center(&self) -> &'o [F; D] {
+        match self.get_kind() {
+            NodeKind::Internal(internal_ref) => &internal_ref.bb.bc.data,
+            NodeKind::Leaf(leaf_ref) => &leaf_ref.bb.bc.data,
+        }
+    }
+
+    /// Get the half width of the node's bounding hypercube, `br` for short.
+    pub fn half_width(&self) -> F {
+        match self.get_kind() {
+            NodeKind::Internal(internal_ref) => internal_ref.bb.br,
+            NodeKind::Leaf(leaf_ref) => leaf_ref.bb.br,
+        }
+    }
+
+    /// Get the weighted average position of the values inside the node, `vc` for short.
+    pub fn value_center(&self) -> &'o [F; D] {
+        match self.get_kind() {
+            NodeKind::Internal(internal_ref) => &internal_ref.vc.data,
+            NodeKind::Leaf(leaf_ref) => &leaf_ref.vc.data,
+        }
+    }
+
+    /// Get the number of values inside the node.
+    pub fn count(&self) -> usize {
+        match self.get_kind() {
+            NodeKind::Internal(internal_ref) => internal_ref.get_values_num_inside(),
+            NodeKind::Leaf(leaf_ref) => leaf_ref.get_values_num_inside(),
+        }
+    }
+
+    /// Get the total weight of the values inside the node.
+    pub fn weight(&self) -> F {
+        match self.get_kind() {
+            NodeKind::Internal(internal_ref) => internal_ref.get_values_weight_inside(),
+            NodeKind::Leaf(leaf_ref) => leaf_ref.get_values_weight_inside(),
+        }
+    }
+
+    /// Get the summary of the node, the same as the one an [crate::OpeningCriterion] sees.
+    pub fn summary(&self) -> NodeSummary<'o, D, F> {
+        match self.get_kind() {
+            NodeKind::Internal(internal_ref) => internal_ref.get_summary(),
+            NodeKind::Leaf(leaf_ref) => leaf_ref.get_summary(),
+        }
+    }
+
+    /// Get the indices of the values held by a leaf, or `None` for an internal node.
+    pub fn values(&self) -> Option<&'o [usize]> {
+        match self.get_kind() {
+            NodeKind::Internal(_) => None,
+            NodeKind::Leaf(leaf_ref) => Some(&leaf_ref.vs),
+        }
+    }
+
+    /// Walk the node and all the nodes below it in depth-first order.
+    ///
+    /// A node comes before its children, and the children are walked in the order of their directions, so the leaves come in the spatial Z-order.
+    pub fn depth_first(&self) -> impl Iterator<Item = NodeRef<'o, D, F>> + 'o {
+        let mut stack = vec![*self];
+        std::iter::from_fn(move || {
+            let curr = stack.pop()?;
+            let len = stack.len();
+            stack.extend(curr.children());
+            stack[len..].reverse();
+            Some(curr)
+        })
+    }
+
+    /// Walk the node and all the nodes below it in breadth-first order.
+    ///
+    /// The nodes come level by level, from the node itself to the deepest ones.
+    pub fn breadth_first(&self) -> impl Iterator<Item = NodeRef<'o, D, F>> + 'o {
+        let mut q = VecDeque::from([*self]);
+        std::iter::from_fn(move || {
+            let curr = q.pop_front()?;
+            q.extend(curr.children());
+            Some(curr)
+        })
+    }
+}
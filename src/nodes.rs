@@ -4,6 +4,7 @@ pub use leaf::Leaf;
 mod internal;
 pub use internal::Internal;
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum NodeIndex {
     In(usize),
     Le(usize),
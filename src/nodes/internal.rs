@@ -1,49 +1,92 @@
-use crate::{boundbox::BoundBox, colvec::ColVec, Udim};
+use crate::{boundbox::BoundBox, charge::Charges, colvec::ColVec, Fnum, NodeSummary, Udim};
+
+#[cfg(feature = "quadrupole")]
+use crate::moment::Moment;
 
 use super::{Leaf, NodeIndex};
 
-pub struct Internal<const D: Udim> {
+pub struct Internal<const D: Udim, F: Fnum> {
     pub(crate) parent: Option<(usize, usize)>,
     pub(crate) nexts: Vec<Option<NodeIndex>>,
 
     count: usize,
-    pub(crate) vc: ColVec<D>,
-    pub(crate) bb: BoundBox<D>,
+    pub(crate) weight: F,
+    pub(crate) vc: ColVec<D, F>,
+    pub(crate) charges: Charges<D, F>,
+    #[cfg(feature = "quadrupole")]
+    pub(crate) moment: Moment<D, F>,
+    pub(crate) bb: BoundBox<D, F>,
 }
 
-impl<const D: Udim> Internal<D> {
+impl<const D: Udim, F: Fnum> Internal<D, F> {
     const DIM_LEN: usize = 2_usize.pow(D as u32);
 
-    pub fn calc_next_dir(&self, vc: &ColVec<D>) -> usize {
+    pub fn calc_next_dir(&self, vc: &ColVec<D, F>) -> usize {
         self.bb.calc_next_dir(vc)
     }
 
-    pub fn calc_child_bb(&self, dir: &usize) -> BoundBox<D> {
+    pub fn calc_child_bb(&self, dir: &usize) -> BoundBox<D, F> {
         self.bb.calc_child_bb(dir)
     }
 
-    pub fn add_value(&mut self, vc: &ColVec<D>) {
+    pub fn add_value(&mut self, vc: &ColVec<D, F>, weight: F, charge: F) {
+        #[cfg(feature = "quadrupole")]
+        let prev_vc = self.vc.clone();
         self.vc
-            .update_online_average_with_one_new_data(self.count, &vc.data);
+            .update_online_average_with_one_new_data(self.weight, &vc.data, weight);
+        #[cfg(feature = "quadrupole")]
+        self.moment
+            .update_with_one_new_data(&prev_vc, &self.vc, vc, weight);
         self.count += 1;
+        self.weight += weight;
+        self.charges.add_charge(vc, charge);
     }
 
-    pub fn sub_value(&mut self, vc: &ColVec<D>) {
-        self.vc
-            .update_online_average_with_one_data_removal(self.count, &vc.data);
+    pub fn sub_value(&mut self, vc: &ColVec<D, F>, weight: F, charge: F) {
+        #[cfg(feature = "quadrupole")]
+        let prev_vc = self.vc.clone();
+        self.vc.update_online_average_with_one_data_removal(
+            self.count,
+            self.weight,
+            &vc.data,
+            weight,
+        );
         self.count -= 1;
+        #[cfg(feature = "quadrupole")]
+        self.moment
+            .update_with_one_data_removal(&prev_vc, &self.vc, vc, weight, self.count);
+        self.weight = if self.count > 0 {
+            self.weight - weight
+        } else {
+            F::ZERO
+        };
+        self.charges.sub_charge(vc, charge);
     }
 
-    pub fn calc_new_internal_with_new_vc(&self, vc: &ColVec<D>) -> (Box<Self>, usize) {
+    pub fn calc_new_internal_with_new_vc(&self, vc: &ColVec<D, F>) -> (Box<Self>, usize) {
         let (new_bb, dir) = self.bb.calc_reverse_expand_bb(vc);
 
-        (
-            Self::new_empty_with_vc_and_bb(new_bb, self.vc.clone(), self.count),
-            dir,
-        )
+        #[allow(unused_mut)]
+        let mut new_box = Self::new_empty_with_vc_and_bb(
+            new_bb,
+            self.vc.clone(),
+            self.count,
+            self.weight,
+            self.charges.clone(),
+        );
+        #[cfg(feature = "quadrupole")]
+        new_box.moment.clone_from(&self.moment);
+
+        (new_box, dir)
     }
 
-    pub fn new_empty_with_vc_and_bb(bb: BoundBox<D>, vc: ColVec<D>, count: usize) -> Box<Self> {
+    pub fn new_empty_with_vc_and_bb(
+        bb: BoundBox<D, F>,
+        vc: ColVec<D, F>,
+        count: usize,
+        weight: F,
+        charges: Charges<D, F>,
+    ) -> Box<Self> {
         let parent: Option<(usize, usize)> = None;
         let mut nexts = Vec::with_capacity(Self::DIM_LEN);
         for _ in 0..Self::DIM_LEN {
@@ -54,14 +97,18 @@ impl<const D: Udim> Internal<D> {
             parent,
             nexts,
             count,
+            weight,
             vc,
+            charges,
+            #[cfg(feature = "quadrupole")]
+            moment: Moment::new_zeros(),
         })
     }
 
     pub fn new_with_leaf_replacement(
         my_i: usize,
         leaf_i: usize,
-        leaf_mut_ref: &mut Leaf<D>,
+        leaf_mut_ref: &mut Leaf<D, F>,
     ) -> Box<Self> {
         let bb = leaf_mut_ref.bb.clone();
         let parent = leaf_mut_ref.parent;
@@ -72,13 +119,17 @@ impl<const D: Udim> Internal<D> {
         curr_box.parent = parent;
         curr_box.vc.clone_from(&leaf_mut_ref.vc);
         curr_box.count = leaf_mut_ref.get_values_num_inside();
+        curr_box.weight = leaf_mut_ref.weight;
+        curr_box.charges.clone_from(&leaf_mut_ref.charges);
+        #[cfg(feature = "quadrupole")]
+        curr_box.moment.clone_from(&leaf_mut_ref.moment);
 
         curr_box.link_leaf_to_dir(next_dir, my_i, leaf_i, leaf_mut_ref);
 
         curr_box
     }
 
-    pub fn new_root(root_bb: BoundBox<D>) -> Box<Self> {
+    pub fn new_root(root_bb: BoundBox<D, F>) -> Box<Self> {
         let mut nexts = Vec::with_capacity(Self::DIM_LEN);
         for _ in 0..Self::DIM_LEN {
             nexts.push(None);
@@ -87,7 +138,11 @@ impl<const D: Udim> Internal<D> {
             parent: None,
             nexts,
             count: 0,
+            weight: F::ZERO,
             vc: ColVec::new_zeros(),
+            charges: Charges::new_zeros(),
+            #[cfg(feature = "quadrupole")]
+            moment: Moment::new_zeros(),
             bb: root_bb,
         })
     }
@@ -98,9 +153,9 @@ impl<const D: Udim> Internal<D> {
         dir: usize,
         my_i: usize,
         leaf_i: usize,
-        leaf_mut_ref: &mut Leaf<D>,
+        leaf_mut_ref: &mut Leaf<D, F>,
     ) {
-        leaf_mut_ref.set_parent(my_i, &self, dir);
+        leaf_mut_ref.set_parent(my_i, self, dir);
         self.nexts[dir] = Some(NodeIndex::Le(leaf_i));
     }
 
@@ -109,6 +164,26 @@ impl<const D: Udim> Internal<D> {
         self.count
     }
 
+    #[inline]
+    pub fn get_values_weight_inside(&self) -> F {
+        self.weight
+    }
+
+    #[inline]
+    pub fn get_summary(&self) -> NodeSummary<'_, D, F> {
+        NodeSummary {
+            bc: &self.bb.bc.data,
+            br: self.bb.br,
+            vc: &self.vc.data,
+            count: self.count,
+            weight: self.weight,
+            #[cfg(feature = "quadrupole")]
+            b2: Some(self.moment.calc_trace()),
+            #[cfg(not(feature = "quadrupole"))]
+            b2: None,
+        }
+    }
+
     #[inline]
     pub fn drop_child(&mut self, dir: usize) -> Option<NodeIndex> {
         self.nexts[dir].take()
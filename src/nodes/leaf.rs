@@ -1,51 +1,95 @@
-use crate::{boundbox::BoundBox, colvec::ColVec, Udim};
+use crate::{boundbox::BoundBox, charge::Charges, colvec::ColVec, Fnum, NodeSummary, Udim};
+
+#[cfg(feature = "quadrupole")]
+use crate::moment::Moment;
 
 use super::Internal;
 
-pub struct Leaf<const D: Udim> {
+pub struct Leaf<const D: Udim, F: Fnum> {
     pub(crate) parent: Option<(usize, usize)>,
 
-    pub(crate) bb: BoundBox<D>,
+    pub(crate) bb: BoundBox<D, F>,
 
-    pub(crate) vc: ColVec<D>,
+    pub(crate) vc: ColVec<D, F>,
+    pub(crate) weight: F,
+    pub(crate) charges: Charges<D, F>,
+    #[cfg(feature = "quadrupole")]
+    pub(crate) moment: Moment<D, F>,
     pub(crate) vs: Vec<usize>,
 }
 
-impl<const D: Udim> Leaf<D> {
-    pub fn new_empty_from_bb(bb: BoundBox<D>) -> Box<Self> {
-        let vc: ColVec<D> = ColVec::new_zeros();
+impl<const D: Udim, F: Fnum> Leaf<D, F> {
+    pub fn new_empty_from_bb(bb: BoundBox<D, F>) -> Box<Self> {
+        let vc: ColVec<D, F> = ColVec::new_zeros();
         let vs: Vec<usize> = Vec::with_capacity(1);
         let parent = None;
-        Box::new(Self { parent, bb, vc, vs })
+        Box::new(Self {
+            parent,
+            bb,
+            vc,
+            weight: F::ZERO,
+            charges: Charges::new_zeros(),
+            #[cfg(feature = "quadrupole")]
+            moment: Moment::new_zeros(),
+            vs,
+        })
     }
 
     pub fn new_empty_from_parent_dir(
-        parent: &mut Internal<D>,
+        parent: &mut Internal<D, F>,
         parent_i: usize,
         dir: usize,
     ) -> Box<Self> {
         let bb = parent.calc_child_bb(&dir);
 
-        let vc: ColVec<D> = ColVec::new_zeros();
+        let vc: ColVec<D, F> = ColVec::new_zeros();
         let vs: Vec<usize> = Vec::with_capacity(1);
         let parent = Some((parent_i, dir));
-        Box::new(Self { parent, bb, vc, vs })
+        Box::new(Self {
+            parent,
+            bb,
+            vc,
+            weight: F::ZERO,
+            charges: Charges::new_zeros(),
+            #[cfg(feature = "quadrupole")]
+            moment: Moment::new_zeros(),
+            vs,
+        })
     }
 
-    pub fn add_value(&mut self, leaf_i: usize, v: &ColVec<D>) -> usize {
+    pub fn add_value(&mut self, leaf_i: usize, v: &ColVec<D, F>, weight: F, charge: F) -> usize {
         let i = self.vs.len();
-        self.vc.update_online_average_with_one_new_data(i, &v.data);
+        #[cfg(feature = "quadrupole")]
+        let prev_vc = self.vc.clone();
+        self.vc
+            .update_online_average_with_one_new_data(self.weight, &v.data, weight);
+        #[cfg(feature = "quadrupole")]
+        self.moment
+            .update_with_one_new_data(&prev_vc, &self.vc, v, weight);
+        self.weight += weight;
+        self.charges.add_charge(v, charge);
         self.vs.push(leaf_i);
         i
     }
 
-    pub fn sub_value(&mut self, child_i: usize, v: &ColVec<D>) -> usize {
+    pub fn sub_value(&mut self, child_i: usize, v: &ColVec<D, F>, weight: F, charge: F) -> usize {
         let len = self.vs.len();
+        #[cfg(feature = "quadrupole")]
+        let prev_vc = self.vc.clone();
         self.vc
-            .update_online_average_with_one_data_removal(len, &v.data);
+            .update_online_average_with_one_data_removal(len, self.weight, &v.data, weight);
+        #[cfg(feature = "quadrupole")]
+        self.moment
+            .update_with_one_data_removal(&prev_vc, &self.vc, v, weight, len - 1);
+        self.weight = if len > 1 {
+            self.weight - weight
+        } else {
+            F::ZERO
+        };
+        self.charges.sub_charge(v, charge);
 
         if child_i + 1 < len {
-            let last_v = self.vs.last().expect("Check length before").clone();
+            let last_v = *self.vs.last().expect("Check length before");
             self.vs[child_i].clone_from(&last_v);
             self.vs.pop(); // Forgot to pop
             last_v
@@ -59,7 +103,25 @@ impl<const D: Udim> Leaf<D> {
         self.vs.len()
     }
 
-    pub fn set_parent(&mut self, parent_i: usize, parent_ref: &Internal<D>, from_dir: usize) {
+    pub fn get_values_weight_inside(&self) -> F {
+        self.weight
+    }
+
+    pub fn get_summary(&self) -> NodeSummary<'_, D, F> {
+        NodeSummary {
+            bc: &self.bb.bc.data,
+            br: self.bb.br,
+            vc: &self.vc.data,
+            count: self.vs.len(),
+            weight: self.weight,
+            #[cfg(feature = "quadrupole")]
+            b2: Some(self.moment.calc_trace()),
+            #[cfg(not(feature = "quadrupole"))]
+            b2: None,
+        }
+    }
+
+    pub fn set_parent(&mut self, parent_i: usize, parent_ref: &Internal<D, F>, from_dir: usize) {
         self.parent = Some((parent_i, from_dir));
 
         self.bb
@@ -6,40 +6,40 @@ use std::{
 use crate::{
     imple::get_ref_from_arr_ref,
     nodes::{Leaf, NodeIndex},
-    BarnesHutTree, ColVec, Fnum, Udim,
+    BarnesHutTree, Fnum, Udim, ValueEntry,
 };
 
 /// # The half-serialized form of Barnes Hut Tree
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct BarnesHutTreeSer<const D: Udim> {
+pub struct BarnesHutTreeSer<const D: Udim, F: Fnum = f64> {
     dim: usize,
     num: usize,
-    vcs: Vec<Fnum>,
-    bcs: Vec<Fnum>,
-    brs: Vec<Fnum>,
+    vcs: Vec<F>,
+    bcs: Vec<F>,
+    brs: Vec<F>,
     ns: Vec<usize>,
     parents: Vec<Option<usize>>,
     from_dirs: Vec<Option<usize>>,
-    vs: Vec<Fnum>,
+    vs: Vec<F>,
     to_leafs: Vec<Option<usize>>,
     idxs: Vec<Option<usize>>,
 }
 
-impl<const D: Udim> BarnesHutTreeSer<D> {
+impl<const D: Udim, F: Fnum> BarnesHutTreeSer<D, F> {
     pub(crate) fn with_num_of_nodes(
         num: usize,
-        vs: &Vec<Box<(ColVec<D>, Option<(usize, usize)>)>>,
-    ) -> BarnesHutTreeSer<D> {
-        let vcs: Vec<Fnum> = Vec::with_capacity(num * D);
-        let bcs: Vec<Fnum> = Vec::with_capacity(num * D);
-        let brs: Vec<Fnum> = Vec::with_capacity(num);
+        vs: &[Box<ValueEntry<D, F>>],
+    ) -> BarnesHutTreeSer<D, F> {
+        let vcs: Vec<F> = Vec::with_capacity(num * D);
+        let bcs: Vec<F> = Vec::with_capacity(num * D);
+        let brs: Vec<F> = Vec::with_capacity(num);
         let ns: Vec<usize> = Vec::with_capacity(num);
         let from_dirs: Vec<Option<usize>> = Vec::with_capacity(num);
         let parents: Vec<Option<usize>> = Vec::with_capacity(num);
 
         let to_leafs: Vec<Option<usize>> = vec![None; vs.len()];
         let idxs: Vec<Option<usize>> = vec![None; vs.len()];
-        let mut ans_vs: Vec<Fnum> = Vec::with_capacity(num * D);
+        let mut ans_vs: Vec<F> = Vec::with_capacity(num * D);
         for v in vs.iter() {
             for d in 0..D {
                 ans_vs.push(v.0.data[d]);
@@ -67,9 +67,9 @@ impl<const D: Udim> BarnesHutTreeSer<D> {
         &mut self,
         parent_opt: Option<usize>,
         from_dir: Option<usize>,
-        vc: &[Fnum; D],
-        bc: &[Fnum; D],
-        br: Fnum,
+        vc: &[F; D],
+        bc: &[F; D],
+        br: F,
         n: usize,
     ) -> usize {
         let curr_i = self.ns.len();
@@ -95,13 +95,13 @@ impl<const D: Udim> BarnesHutTreeSer<D> {
     pub fn get_num(&self) -> &usize {
         &self.num
     }
-    pub fn get_vcs(&self) -> &Vec<Fnum> {
+    pub fn get_vcs(&self) -> &Vec<F> {
         &self.vcs
     }
-    pub fn get_bcs(&self) -> &Vec<Fnum> {
+    pub fn get_bcs(&self) -> &Vec<F> {
         &self.bcs
     }
-    pub fn get_brs(&self) -> &Vec<Fnum> {
+    pub fn get_brs(&self) -> &Vec<F> {
         &self.brs
     }
     pub fn get_ns(&self) -> &Vec<usize> {
@@ -113,7 +113,7 @@ impl<const D: Udim> BarnesHutTreeSer<D> {
     pub fn get_from_dirs(&self) -> &Vec<Option<usize>> {
         &self.from_dirs
     }
-    pub fn get_vs(&self) -> &Vec<Fnum> {
+    pub fn get_vs(&self) -> &Vec<F> {
         &self.vs
     }
     pub fn get_to_leafs(&self) -> &Vec<Option<usize>> {
@@ -127,24 +127,24 @@ impl<const D: Udim> BarnesHutTreeSer<D> {
 /// Serialize the tree into an intermediate form for comparing and further serialization.
 ///
 /// To make the serialization process simpler, I design to let thee [BarnesHutTree] first serialize into the intermediate form `BarnesHutTreeSer` for `serde_json`'s auto "derive" and testing. I guess the `BarnesHutTreeSer` also makes the serialized `JSON` form relatively smaller in size due to less "struct" with "Strings" to represent fields.
-impl<const D: Udim> BarnesHutTree<D> {
-    pub fn calc_serialized(&self) -> BarnesHutTreeSer<D> {
+impl<const D: Udim, F: Fnum> BarnesHutTree<D, F> {
+    pub fn calc_serialized(&self) -> BarnesHutTreeSer<D, F> {
         let nodes_num = self.get_total_nodes_num();
-        let mut ans = BarnesHutTreeSer::<D>::with_num_of_nodes(nodes_num, &self.vs);
+        let mut ans = BarnesHutTreeSer::<D, F>::with_num_of_nodes(nodes_num, &self.vs);
         let mut dq: VecDeque<(usize, Option<(usize, usize)>)> = VecDeque::with_capacity(nodes_num);
 
-        fn add_leaf<const D: Udim>(
+        fn add_leaf<const D: Udim, F: Fnum>(
             parent_opt: Option<usize>,
             from_dir: Option<usize>,
-            leaf_ref: &Leaf<D>,
-            ans: &mut BarnesHutTreeSer<D>,
+            leaf_ref: &Leaf<D, F>,
+            ans: &mut BarnesHutTreeSer<D, F>,
         ) {
             let curr_i = ans.add_node(
                 parent_opt,
                 from_dir,
                 &leaf_ref.vc.data,
                 &leaf_ref.bb.bc.data,
-                leaf_ref.bb.br.clone(),
+                leaf_ref.bb.br,
                 leaf_ref.vs.len(),
             );
 
@@ -156,7 +156,7 @@ impl<const D: Udim> BarnesHutTree<D> {
 
         match &self.root {
             Some(NodeIndex::In(next_internal_i)) => {
-                dq.push_back((next_internal_i.clone(), None));
+                dq.push_back((*next_internal_i, None));
             }
             Some(NodeIndex::Le(next_leaf_i)) => {
                 add_leaf(
@@ -183,7 +183,7 @@ impl<const D: Udim> BarnesHutTree<D> {
                 from_dir,
                 &curr_ref.vc.data,
                 &curr_ref.bb.bc.data,
-                curr_ref.bb.br.clone(),
+                curr_ref.bb.br,
                 curr_ref.get_values_num_inside(),
             );
             for (from_dir, next) in curr_ref.nexts.iter().enumerate() {
@@ -207,7 +207,7 @@ impl<const D: Udim> BarnesHutTree<D> {
     }
 }
 
-impl<const D: Udim> serde::Serialize for BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum + serde::Serialize> serde::Serialize for BarnesHutTree<D, F> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
@@ -217,7 +217,7 @@ impl<const D: Udim> serde::Serialize for BarnesHutTree<D> {
     }
 }
 
-impl<const D: Udim> Debug for BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum + serde::Serialize> Debug for BarnesHutTree<D, F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
             "{}",
@@ -230,7 +230,7 @@ impl<const D: Udim> Debug for BarnesHutTree<D> {
     }
 }
 
-impl<const D: Udim> Display for BarnesHutTree<D> {
+impl<const D: Udim, F: Fnum + serde::Serialize> Display for BarnesHutTree<D, F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
             "{}",
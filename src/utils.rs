@@ -4,37 +4,70 @@
 //!
 //! The output closures from the factory functions are from Hu, Y. (2005). Efficient, high-quality force-directed graph drawing. _Mathematica journal, 10_(1), 37-71, mentioned on the main page of the crate. These functions are designed to calculate the repulsive forces and, via force simulation, find nice graph node positions.
 //!
+//! The module also provides force, displacement, and potential energy calculation function factories for Newtonian gravity with Plummer or cubic spline softening, and for the Coulomb interaction. The [tsne] module provides the helpers of Barnes-Hut t-SNE.
+//!
+//! ## Float types
+//!
+//! The factories from the first versions of the crate build `f64` closures, and each of them has a variant with the `_of` suffix taking the float type as its second template parameter. The other factories are generic over the float type, which is given at the call site, for example, `factory_of_plummer_gravity_force_calc_fn::<2, f32>`.
+//!
 //! ## When a distance gets too close
 //!
 //! If a distance falls under `1e-8`, the closure will use `1e-8` to proceed with the calculations.
 
-use crate::{Fnum, Udim};
+use crate::{Fnum, NodeSummary, OpeningCriterion, Udim};
+
+mod gravity;
+pub use gravity::{
+    factory_of_plummer_gravity_displacement_calc_fn, factory_of_plummer_gravity_force_calc_fn,
+    factory_of_plummer_gravity_force_with_potential_calc_fn,
+    factory_of_plummer_gravity_potential_calc_fn, factory_of_spline_gravity_displacement_calc_fn,
+    factory_of_spline_gravity_force_calc_fn,
+    factory_of_spline_gravity_force_with_potential_calc_fn,
+    factory_of_spline_gravity_potential_calc_fn,
+};
+
+pub mod tsne;
+
+mod coulomb;
+pub use coulomb::{
+    factory_of_coulomb_displacement_calc_fn, factory_of_coulomb_force_calc_fn,
+    factory_of_coulomb_force_with_potential_calc_fn, factory_of_coulomb_potential_calc_fn,
+};
 
-const DEFAULT_MIN_DIS: Fnum = 1e-8;
+const DEFAULT_MIN_DIS: f64 = 1e-8;
 
-fn calc_v0_to_v1_diff<const D: Udim>(v0: &[Fnum; D], v1: &[Fnum; D]) -> [Fnum; D] {
-    let mut ans = [0.0; D];
+fn calc_v0_to_v1_diff<const D: Udim, F: Fnum>(v0: &[F; D], v1: &[F; D]) -> [F; D] {
+    let mut ans = [F::ZERO; D];
     for d in 0..D {
         ans[d] = v1[d] - v0[d];
     }
     ans
 }
 
-fn calc_sum_of_squared<const D: Udim>(v: &[Fnum; D]) -> Fnum {
-    let mut ans_f64 = 0.0;
+fn calc_sum_of_squared<const D: Udim, F: Fnum>(v: &[F; D]) -> F {
+    let mut ans_f64 = F::ZERO;
     for curr_v in v.iter() {
-        ans_f64 += curr_v * curr_v;
+        ans_f64 += *curr_v * *curr_v;
     }
     ans_f64
 }
 
+fn calc_dis_at_least_min<const D: Udim, F: Fnum>(v0: &[F; D], v1: &[F; D]) -> F {
+    let dis_pow2 = calc_sum_of_squared(&calc_v0_to_v1_diff(v0, v1));
+    if dis_pow2.is_finite() && dis_pow2 > F::from_f64(DEFAULT_MIN_DIS) {
+        dis_pow2.sqrt()
+    } else {
+        F::from_f64(DEFAULT_MIN_DIS).sqrt()
+    }
+}
+
 /// This function is the factory of the repulsive displacement calculation function.
 ///
 /// The function returns a closure defined by parameters `k` and `c`.
 ///
 /// The returned closure takes the position of the target value, the mean position of a group of values, the size of the group, and the to-calculate answer's mutable reference.
 ///
-/// The returned closure updates the third argument representing the answer displacement when calling it with the position of the target value, the average position of the values, and the number of values of a "far" super node.
+/// The returned closure updates the third argument representing the answer displacement when calling it with the position of the target value, the average position of the values, and the number of values of a "far" super node. The closure is a [crate::Kernel], so it can be passed to the tree's calculation methods directly.
 ///
 /// The repulsive force between the target value and the super node is the number of values contained in the super node times `c` and the square of `k` divided by the distance between the value and the average center of the values in the super node. The repulsive displacement in one update is, therefore, the force times the direction of the super node to the target value, which is the vector of target value minus super node value center times the number of values contained in the super node times `c` and square of `k` divided by the distance squared.
 ///
@@ -58,27 +91,107 @@ fn calc_sum_of_squared<const D: Udim>(v: &[Fnum; D]) -> Fnum {
 /// ```
 ///
 pub fn factory_of_repulsive_displacement_calc_fn<const D: Udim>(
-    k: Fnum,
-    c: Fnum,
-) -> impl Fn(&[Fnum; D], &[Fnum; D], usize, &mut [Fnum; D]) {
-    move |curr_v_ref: &[Fnum; D],
-          other_vc_ref: &[Fnum; D],
-          num: usize,
-          ans_mut_ref: &mut [Fnum; D]| {
+    k: f64,
+    c: f64,
+) -> impl Fn(&[f64; D], &[f64; D], usize, &mut [f64; D]) {
+    factory_of_repulsive_displacement_calc_fn_of::<D, f64>(k, c)
+}
+
+///
+/// This function builds the closure of [factory_of_repulsive_displacement_calc_fn] with `k`, `c`, and the positions in the float type `F`.
+///
+pub fn factory_of_repulsive_displacement_calc_fn_of<const D: Udim, F: Fnum>(
+    k: F,
+    c: F,
+) -> impl Fn(&[F; D], &[F; D], usize, &mut [F; D]) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], num: usize, ans_mut_ref: &mut [F; D]| {
         let diff = calc_v0_to_v1_diff(other_vc_ref, curr_v_ref);
         let dis_pow2 = calc_sum_of_squared(&diff);
-        let dis_pow2 = if dis_pow2.is_finite() && dis_pow2 > DEFAULT_MIN_DIS {
+        let dis_pow2 = if dis_pow2.is_finite() && dis_pow2 > F::from_f64(DEFAULT_MIN_DIS) {
             dis_pow2
         } else {
-            DEFAULT_MIN_DIS
+            F::from_f64(DEFAULT_MIN_DIS)
         };
-        let scalar = num as Fnum * k * k * c / dis_pow2;
+        let scalar = F::from_usize(num) * k * k * c / dis_pow2;
         for d in 0..D {
             ans_mut_ref[d] += diff[d] * scalar;
         }
     }
 }
 
+/// This function is the factory of the error estimation function of [factory_of_repulsive_displacement_calc_fn].
+///
+/// The function returns a closure defined by the same parameters `k` and `c`, for [crate::BarnesHutTree::calc_force_on_value_with_error].
+///
+/// The returned closure takes the position of the target value and the summary of a super node, and returns the estimated magnitude of the error of treating the super node as its values sitting at the average position. As the first order error around the average position cancels out, the error is estimated by the second order term, which is the number of values in the super node times `c` and the square of `k` times the square of `bmax` divided by the cube of `dis`, where `dis` is the distance between the target value and the average position, and `bmax` is [crate::NodeSummary::calc_bmax].
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let error_fn = zbht::utils::factory_of_repulsive_displacement_error_fn::<2, f64>(1.0, 0.2);
+///
+/// let node = zbht::NodeSummary { bc: &[4.0, 0.0], br: 1.0, vc: &[4.0, 0.0], count: 2, weight: 2.0, b2: None };
+///
+/// let bmax = 2.0_f64.sqrt();
+/// let error = 2.0 * 0.2 * bmax * bmax / 4.0_f64.powi(3);
+/// assert!((error_fn(&[0.0, 0.0], &node) - error).abs() < 1e-12);
+/// ```
+///
+pub fn factory_of_repulsive_displacement_error_fn<const D: Udim, F: Fnum>(
+    k: F,
+    c: F,
+) -> impl Fn(&[F; D], &NodeSummary<'_, D, F>) -> F {
+    move |curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>| -> F {
+        let bmax = node.calc_bmax();
+        let dis = calc_dis_at_least_min(curr_v_ref, node.vc);
+        F::from_usize(node.count) * k * k * c * bmax * bmax / (dis * dis * dis)
+    }
+}
+
+///
+/// This function is the factory of the repulsive displacement pair function, which is a [crate::SymmetricKernel] when wrapped in [crate::PairKernel].
+///
+/// The function returns a closure defined by parameters `k` and `c`, the same as [factory_of_repulsive_displacement_calc_fn] with a group of one value. The returned closure takes the positions of two values and returns the repulsive displacement of the first value. The displacement of the second value is exactly the opposite.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let k = 1.0;
+/// let c = 0.2;
+///
+/// let pair_fn = zbht::utils::factory_of_repulsive_displacement_pair_fn::<2, f64>(k, c);
+///
+/// let diff = -2.0;
+/// let dis = 2.0;
+///
+/// assert_eq!(pair_fn(&[-1.0,0.0],&[1.0,0.0]), [(diff * k * k * c) / (dis * dis), 0.0]);
+/// assert_eq!(pair_fn(&[1.0,0.0],&[-1.0,0.0]), [-(diff * k * k * c) / (dis * dis), 0.0]);
+/// ```
+///
+pub fn factory_of_repulsive_displacement_pair_fn<const D: Udim, F: Fnum>(
+    k: F,
+    c: F,
+) -> impl Fn(&[F; D], &[F; D]) -> [F; D] {
+    move |v0_ref: &[F; D], v1_ref: &[F; D]| -> [F; D] {
+        let mut diff = calc_v0_to_v1_diff(v1_ref, v0_ref);
+        let dis_pow2 = calc_sum_of_squared(&diff);
+        let dis_pow2 = if dis_pow2.is_finite() && dis_pow2 > F::from_f64(DEFAULT_MIN_DIS) {
+            dis_pow2
+        } else {
+            F::from_f64(DEFAULT_MIN_DIS)
+        };
+        let scalar = k * k * c / dis_pow2;
+        for diff_d in diff.iter_mut() {
+            *diff_d *= scalar;
+        }
+        diff
+    }
+}
+
 ///
 /// This function is the factory of the repulsive displacement and energy calculation function.
 ///
@@ -108,22 +221,31 @@ pub fn factory_of_repulsive_displacement_calc_fn<const D: Udim>(
 /// assert_eq!(ans_displacement_and_energy, ([(diff * k * k * c) / (dis * dis), 0.0],(super_node_size as f64 * k * k * c).powi(2) / (dis.powi(2))));
 /// ```
 ///
+#[allow(clippy::type_complexity)]
 pub fn factory_of_repulsive_displacement_with_energy_calc_fn<const D: Udim>(
-    k: Fnum,
-    c: Fnum,
-) -> impl Fn(&[Fnum; D], &[Fnum; D], usize, &mut ([Fnum; D], Fnum)) {
-    move |curr_v_ref: &[Fnum; D],
-          other_vc_ref: &[Fnum; D],
-          num: usize,
-          ans_mut_ref: &mut ([Fnum; D], Fnum)| {
+    k: f64,
+    c: f64,
+) -> impl Fn(&[f64; D], &[f64; D], usize, &mut ([f64; D], f64)) {
+    factory_of_repulsive_displacement_with_energy_calc_fn_of::<D, f64>(k, c)
+}
+
+///
+/// This function builds the closure of [factory_of_repulsive_displacement_with_energy_calc_fn] over the float type `F`, which is also the type of the accumulated energy.
+///
+#[allow(clippy::needless_range_loop, clippy::type_complexity)]
+pub fn factory_of_repulsive_displacement_with_energy_calc_fn_of<const D: Udim, F: Fnum>(
+    k: F,
+    c: F,
+) -> impl Fn(&[F; D], &[F; D], usize, &mut ([F; D], F)) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], num: usize, ans_mut_ref: &mut ([F; D], F)| {
         let diff = calc_v0_to_v1_diff(other_vc_ref, curr_v_ref);
         let dis_pow2 = calc_sum_of_squared(&diff);
-        let dis_pow2 = if dis_pow2.is_finite() && dis_pow2 > DEFAULT_MIN_DIS {
+        let dis_pow2 = if dis_pow2.is_finite() && dis_pow2 > F::from_f64(DEFAULT_MIN_DIS) {
             dis_pow2
         } else {
-            DEFAULT_MIN_DIS
+            F::from_f64(DEFAULT_MIN_DIS)
         };
-        let num_fnum = num as Fnum;
+        let num_fnum = F::from_usize(num);
         ans_mut_ref.1 += (num_fnum * k * k * c).powi(2) / dis_pow2;
         let scalar = num_fnum * k * k * c / dis_pow2;
         for d in 0..D {
@@ -152,16 +274,263 @@ pub fn factory_of_repulsive_displacement_with_energy_calc_fn<const D: Udim>(
 /// ```
 ///
 pub fn factory_of_is_super_node_fn<const D: Udim>(
-    theta: Fnum,
-) -> impl Fn(&[Fnum; D], &[Fnum; D], Fnum) -> bool {
-    move |curr_v_ref: &[Fnum; D], super_bc_ref: &[Fnum; D], super_half_w: Fnum| -> bool {
+    theta: f64,
+) -> impl Fn(&[f64; D], &[f64; D], f64) -> bool {
+    factory_of_is_super_node_fn_of::<D, f64>(theta)
+}
+
+///
+/// This function builds the closure of [factory_of_is_super_node_fn] for the positions and the half widths in the float type `F`.
+///
+pub fn factory_of_is_super_node_fn_of<const D: Udim, F: Fnum>(
+    theta: F,
+) -> impl Fn(&[F; D], &[F; D], F) -> bool {
+    move |curr_v_ref: &[F; D], super_bc_ref: &[F; D], super_half_w: F| -> bool {
         let diff = calc_v0_to_v1_diff(curr_v_ref, super_bc_ref);
         let dis_pow2 = calc_sum_of_squared(&diff);
-        let dis = if dis_pow2.is_finite() && dis_pow2 > DEFAULT_MIN_DIS {
+        let dis = if dis_pow2.is_finite() && dis_pow2 > F::from_f64(DEFAULT_MIN_DIS) {
+            dis_pow2.sqrt()
+        } else {
+            F::from_f64(DEFAULT_MIN_DIS).sqrt()
+        };
+        ((super_half_w * F::TWO) / dis) <= theta
+    }
+}
+
+///
+/// This function passes a closure through as an opening criterion.
+///
+/// The calculation methods take any [OpeningCriterion], so the argument types of a closure written inline cannot be inferred from them. Wrapping the closure with this function tells its signature, `Fn(&[F; D], &[F; D], F) -> bool`, taking the position of the target, the average position of the values in the node, and the half width of the node.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// use zbht::BarnesHutTree as BHTree;
+///
+/// let bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0,0.0],2.0, &[[-1.0,1.0],[1.0,1.0]]);
+///
+/// let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+///
+/// let mut ans_displacements = vec![[0.0; 2]; 2];
+/// assert!(bht.calc_force_on_all_values(zbht::utils::criterion_fn(|_, _, _| false), &calc_fn, &mut ans_displacements));
+///
+/// assert_eq!(ans_displacements, vec![[(-2.0 * 0.2) / (2.0 * 2.0), 0.0], [(2.0 * 0.2) / (2.0 * 2.0), 0.0]]);
+/// ```
+///
+pub fn criterion_fn<const D: Udim, F: Fnum>(
+    is_super_node: impl Fn(&[F; D], &[F; D], F) -> bool,
+) -> impl Fn(&[F; D], &[F; D], F) -> bool {
+    is_super_node
+}
+
+#[derive(Clone, Copy)]
+struct BarnesHutCriterion<F: Fnum> {
+    theta: F,
+}
+
+impl<const D: Udim, F: Fnum> OpeningCriterion<D, F> for BarnesHutCriterion<F> {
+    #[inline]
+    fn is_super_node(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>) -> bool {
+        ((node.br * F::TWO) / calc_dis_at_least_min(curr_v_ref, node.vc)) <= self.theta
+    }
+}
+
+///
+/// This function is the factory of the classic Barnes-Hut opening criterion.
+///
+/// The returned criterion accepts a super node if the width of its bounding hypercube divided by the distance between the target and the average position of the values inside is less than `theta`, the same as [factory_of_is_super_node_fn].
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// use zbht::{NodeSummary, OpeningCriterion};
+///
+/// let criterion = zbht::utils::factory_of_barnes_hut_criterion::<2, f64>(1.2);
+///
+/// let node = NodeSummary { bc: &[4.0,3.0], br: 5.0, vc: &[4.0,3.0], count: 2, weight: 2.0, b2: None };
+/// assert_eq!(criterion.is_super_node(&[0.0,0.0], &node), (2.0 * 5.0 / 5.0) <= 1.2);
+/// ```
+///
+pub fn factory_of_barnes_hut_criterion<const D: Udim, F: Fnum>(
+    theta: F,
+) -> impl OpeningCriterion<D, F> + Copy {
+    BarnesHutCriterion { theta }
+}
+
+#[derive(Clone, Copy)]
+struct MinDistanceCriterion<F: Fnum> {
+    theta: F,
+}
+
+impl<const D: Udim, F: Fnum> OpeningCriterion<D, F> for MinDistanceCriterion<F> {
+    #[inline]
+    fn is_super_node(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>) -> bool {
+        (node.calc_bmax() / calc_dis_at_least_min(curr_v_ref, node.vc)) <= self.theta
+    }
+}
+
+///
+/// This function is the factory of the min-distance (`bmax`) opening criterion.
+///
+/// The returned criterion accepts a super node if the max distance from the average position of the values inside to the corners of its bounding hypercube, see [NodeSummary::calc_bmax], divided by the distance between the target and the average position is less than `theta`. Different from the classic criterion, a node whose values gather at one side of the hypercube is opened when the target is close to the other side.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// use zbht::{NodeSummary, OpeningCriterion};
+///
+/// let criterion = zbht::utils::factory_of_min_distance_criterion::<2, f64>(1.0);
+///
+/// let node = NodeSummary { bc: &[10.0,0.0], br: 1.0, vc: &[9.0,0.0], count: 2, weight: 2.0, b2: None };
+/// assert_eq!(node.calc_bmax(), (2.0_f64 * 2.0 + 1.0 * 1.0).sqrt());
+/// assert!(criterion.is_super_node(&[0.0,0.0], &node));
+/// assert!(!criterion.is_super_node(&[7.0,0.0], &node));
+/// ```
+///
+pub fn factory_of_min_distance_criterion<const D: Udim, F: Fnum>(
+    theta: F,
+) -> impl OpeningCriterion<D, F> + Copy {
+    MinDistanceCriterion { theta }
+}
+
+#[cfg(feature = "quadrupole")]
+#[derive(Clone, Copy)]
+struct SalmonWarrenCriterion<F: Fnum> {
+    delta: F,
+}
+
+#[cfg(feature = "quadrupole")]
+impl<const D: Udim, F: Fnum> OpeningCriterion<D, F> for SalmonWarrenCriterion<F> {
+    #[inline]
+    fn is_super_node(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>) -> bool {
+        let bmax = node.calc_bmax();
+        let bmax_pow2 = bmax * bmax;
+        let b2 = node.b2.unwrap_or(node.weight * bmax_pow2);
+        let min_dis = bmax * F::HALF
+            + (bmax_pow2 * F::HALF * F::HALF + (F::from_f64(3.0) * b2 / self.delta).sqrt()).sqrt();
+        calc_dis_at_least_min(curr_v_ref, node.vc) > min_dis
+    }
+}
+
+///
+/// This function is the factory of the Salmon-Warren opening criterion.
+///
+/// This function requires the `quadrupole` feature, which tracks the second moments of the nodes. The returned criterion accepts a super node if the distance between the target and the average position of the values inside is greater than `bmax / 2 + sqrt(bmax^2 / 4 + sqrt(3 * B2 / delta))`, where `bmax` is [NodeSummary::calc_bmax], `B2` is [NodeSummary::b2], and `delta` is the tolerated absolute error of the force. The summaries from the tree always have `B2` with the feature.
+///
+/// A [NodeSummary] built by hand with `b2: None` falls back to the upper bound of `B2`, the total weight times `bmax` squared. The fallback never accepts a node the real criterion would open, but it is a different, stricter criterion depending only on the size of the node, and it opens more nodes than the Salmon-Warren criterion does.
+///
+/// Salmon, J. K., & Warren, M. S. (1994). Skeletons from the treecode closet. _Journal of Computational Physics, 111_(1), 136-155.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// use zbht::{NodeSummary, OpeningCriterion};
+///
+/// let criterion = zbht::utils::factory_of_salmon_warren_criterion::<2, f64>(0.01);
+///
+/// // Four values with a weight of one at the corners of the hypercube.
+/// let node = NodeSummary { bc: &[0.0,0.0], br: 0.5, vc: &[0.0,0.0], count: 4, weight: 4.0, b2: Some(2.0) };
+/// assert!(criterion.is_super_node(&[100.0,0.0], &node));
+/// assert!(!criterion.is_super_node(&[2.0,0.0], &node));
+/// ```
+///
+#[cfg(feature = "quadrupole")]
+pub fn factory_of_salmon_warren_criterion<const D: Udim, F: Fnum>(
+    delta: F,
+) -> impl OpeningCriterion<D, F> + Copy {
+    SalmonWarrenCriterion { delta }
+}
+
+#[derive(Clone, Copy)]
+struct RelativeCriterion<F: Fnum> {
+    alpha: F,
+    g: F,
+    acc_magnitude: F,
+}
+
+impl<const D: Udim, F: Fnum> OpeningCriterion<D, F> for RelativeCriterion<F> {
+    #[inline]
+    fn is_super_node(&self, curr_v_ref: &[F; D], node: &NodeSummary<'_, D, F>) -> bool {
+        let dis = calc_dis_at_least_min(curr_v_ref, node.vc);
+        let dis_pow2 = dis * dis;
+        let w = node.br * F::TWO;
+        self.g * node.weight * w * w / (dis_pow2 * dis_pow2) <= self.alpha * self.acc_magnitude
+    }
+}
+
+///
+/// This function is the factory of the relative (force-magnitude based) opening criterion.
+///
+/// The returned criterion accepts a super node if the estimated error of its force, `g * weight / dis^2 * (width / dis)^2`, is less than `alpha` times the magnitude of the target's force (acceleration) `acc_magnitude`, usually from the previous step of a simulation. Since the magnitude belongs to one target, create a criterion for each target value.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// use zbht::{NodeSummary, OpeningCriterion};
+///
+/// let criterion = zbht::utils::factory_of_relative_criterion::<2, f64>(0.01, 1.0, 1.0);
+///
+/// let node = NodeSummary { bc: &[0.0,0.0], br: 0.5, vc: &[0.0,0.0], count: 4, weight: 4.0, b2: None };
+/// assert!(criterion.is_super_node(&[20.0,0.0], &node));
+/// assert!(!criterion.is_super_node(&[2.0,0.0], &node));
+/// ```
+///
+pub fn factory_of_relative_criterion<const D: Udim, F: Fnum>(
+    alpha: F,
+    g: F,
+    acc_magnitude: F,
+) -> impl OpeningCriterion<D, F> + Copy {
+    RelativeCriterion {
+        alpha,
+        g,
+        acc_magnitude,
+    }
+}
+
+///
+/// This function is the factory of is-well-separated function for the dual-tree traversal.
+///
+/// This function returns a closure defined by parameter `theta`. The closure will return `true` if the sum of the widths of two nodes divided by the distance between the average positions of the values in them is less than `theta.`
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let theta = 1.2;
+///
+/// let is_well_separated_fn = zbht::utils::factory_of_is_well_separated_fn::<2, f64>(theta);
+///
+/// let half_width_0 = 2.0;
+/// let half_width_1 = 1.0;
+/// let dis = 5.0;
+/// assert_eq!(
+///     is_well_separated_fn(&[0.0,0.0], half_width_0, &[4.0,3.0], half_width_1),
+///     (2.0 * (half_width_0 + half_width_1) / dis) <= theta
+/// );
+/// ```
+///
+pub fn factory_of_is_well_separated_fn<const D: Udim, F: Fnum>(
+    theta: F,
+) -> impl Fn(&[F; D], F, &[F; D], F) -> bool {
+    move |vc_0_ref: &[F; D], half_w_0: F, vc_1_ref: &[F; D], half_w_1: F| -> bool {
+        let diff = calc_v0_to_v1_diff(vc_0_ref, vc_1_ref);
+        let dis_pow2 = calc_sum_of_squared(&diff);
+        let dis = if dis_pow2.is_finite() && dis_pow2 > F::from_f64(DEFAULT_MIN_DIS) {
             dis_pow2.sqrt()
         } else {
-            DEFAULT_MIN_DIS.sqrt()
+            F::from_f64(DEFAULT_MIN_DIS).sqrt()
         };
-        ((super_half_w * 2.0) / dis) <= theta
+        (((half_w_0 + half_w_1) * F::TWO) / dis) <= theta
     }
 }
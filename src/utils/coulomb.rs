@@ -0,0 +1,194 @@
+//! # Coulomb interaction
+//!
+//! The closures are for [crate::BarnesHutTree::calc_charged_force_on_value], taking the position of the target value, the positive-charge-weighted center and the total positive charge, the negative-charge-weighted center and the total negative charge of a super node (or the other value), and the answer's mutable reference.
+//!
+//! The force is the one on a target of unit charge, i.e., the electric field, pointing away from positive charges and towards negative charges. Multiply it by the target's charge for the actual force. In the same way, the potential energy is the electric potential at the target. The displacement is the force times the parameter `step`.
+
+use crate::{Fnum, Udim};
+
+use super::{calc_sum_of_squared, calc_v0_to_v1_diff, DEFAULT_MIN_DIS};
+
+/// Calculate the field and the potential of one side of the charges at the target.
+fn calc_coulomb<const D: Udim, F: Fnum>(
+    curr_v_ref: &[F; D],
+    qc_ref: &[F; D],
+    q: F,
+    k: F,
+) -> ([F; D], F) {
+    if q == F::ZERO {
+        return ([F::ZERO; D], F::ZERO);
+    }
+    let mut diff = calc_v0_to_v1_diff(qc_ref, curr_v_ref);
+    let dis_pow2 = calc_sum_of_squared(&diff);
+    let dis_pow2 = if dis_pow2.is_finite() && dis_pow2 > F::from_f64(DEFAULT_MIN_DIS) {
+        dis_pow2
+    } else {
+        F::from_f64(DEFAULT_MIN_DIS)
+    };
+    let dis = dis_pow2.sqrt();
+    let potential = k * q / dis;
+    let scalar = potential / dis_pow2;
+    for diff_d in diff.iter_mut() {
+        *diff_d *= scalar;
+    }
+    (diff, potential)
+}
+
+/// Calculate the field and the potential of both sides of the charges at the target.
+fn calc_coulomb_of_both<const D: Udim, F: Fnum>(
+    curr_v_ref: &[F; D],
+    pos_vc_ref: &[F; D],
+    pos_q: F,
+    neg_vc_ref: &[F; D],
+    neg_q: F,
+    k: F,
+) -> ([F; D], F) {
+    let (mut field, pos_potential) = calc_coulomb(curr_v_ref, pos_vc_ref, pos_q, k);
+    let (neg_field, neg_potential) = calc_coulomb(curr_v_ref, neg_vc_ref, neg_q, k);
+    for d in 0..D {
+        field[d] += neg_field[d];
+    }
+    (field, pos_potential + neg_potential)
+}
+
+/// This function is the factory of the Coulomb force calculation function.
+///
+/// For each side of the charges, the returned closure adds `k * q * diff / dis^3` to the answer, where `diff` is the vector from the charge center to the target.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_coulomb_force_calc_fn::<2, f64>(1.0);
+///
+/// let mut ans_force = [0.0; 2];
+/// calc_fn(&[0.0, 0.0], &[2.0, 0.0], 2.0, &[0.0, 1.0], -1.0, &mut ans_force);
+///
+/// // Pushed away by the positive charge, and pulled by the negative one.
+/// assert!((ans_force[0] - 2.0 * -2.0 / 8.0).abs() < 1e-12);
+/// assert!((ans_force[1] - (-1.0) * -1.0 / 1.0).abs() < 1e-12);
+/// ```
+///
+#[allow(clippy::type_complexity)]
+pub fn factory_of_coulomb_force_calc_fn<const D: Udim, F: Fnum>(
+    k: F,
+) -> impl Fn(&[F; D], &[F; D], F, &[F; D], F, &mut [F; D]) {
+    move |curr_v_ref: &[F; D],
+          pos_vc_ref: &[F; D],
+          pos_q: F,
+          neg_vc_ref: &[F; D],
+          neg_q: F,
+          ans_mut_ref: &mut [F; D]| {
+        let (field, _) = calc_coulomb_of_both(curr_v_ref, pos_vc_ref, pos_q, neg_vc_ref, neg_q, k);
+        for d in 0..D {
+            ans_mut_ref[d] += field[d];
+        }
+    }
+}
+
+/// This function is the factory of the Coulomb displacement calculation function.
+///
+/// The returned closure adds the force of [factory_of_coulomb_force_calc_fn] times `step` to the answer.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_coulomb_displacement_calc_fn::<2, f64>(1.0, 0.5);
+///
+/// let mut ans_displacement = [0.0; 2];
+/// calc_fn(&[0.0, 0.0], &[2.0, 0.0], 2.0, &[0.0, 0.0], 0.0, &mut ans_displacement);
+///
+/// assert!((ans_displacement[0] - 0.5 * 2.0 * -2.0 / 8.0).abs() < 1e-12);
+/// assert_eq!(ans_displacement[1], 0.0);
+/// ```
+///
+#[allow(clippy::type_complexity)]
+pub fn factory_of_coulomb_displacement_calc_fn<const D: Udim, F: Fnum>(
+    k: F,
+    step: F,
+) -> impl Fn(&[F; D], &[F; D], F, &[F; D], F, &mut [F; D]) {
+    move |curr_v_ref: &[F; D],
+          pos_vc_ref: &[F; D],
+          pos_q: F,
+          neg_vc_ref: &[F; D],
+          neg_q: F,
+          ans_mut_ref: &mut [F; D]| {
+        let (field, _) = calc_coulomb_of_both(curr_v_ref, pos_vc_ref, pos_q, neg_vc_ref, neg_q, k);
+        for d in 0..D {
+            ans_mut_ref[d] += field[d] * step;
+        }
+    }
+}
+
+/// This function is the factory of the Coulomb potential calculation function.
+///
+/// For each side of the charges, the returned closure adds `k * q / dis` to the answer.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_coulomb_potential_calc_fn::<2, f64>(1.0);
+///
+/// let mut ans_potential = 0.0;
+/// calc_fn(&[0.0, 0.0], &[2.0, 0.0], 2.0, &[0.0, 1.0], -1.0, &mut ans_potential);
+///
+/// assert!((ans_potential - (2.0 / 2.0 + -1.0 / 1.0)).abs() < 1e-12);
+/// ```
+///
+pub fn factory_of_coulomb_potential_calc_fn<const D: Udim, F: Fnum>(
+    k: F,
+) -> impl Fn(&[F; D], &[F; D], F, &[F; D], F, &mut F) {
+    move |curr_v_ref: &[F; D],
+          pos_vc_ref: &[F; D],
+          pos_q: F,
+          neg_vc_ref: &[F; D],
+          neg_q: F,
+          ans_mut_ref: &mut F| {
+        let (_, potential) =
+            calc_coulomb_of_both(curr_v_ref, pos_vc_ref, pos_q, neg_vc_ref, neg_q, k);
+        *ans_mut_ref += potential;
+    }
+}
+
+/// This function is the factory of the Coulomb force and potential calculation function.
+///
+/// The returned closure updates both the force of [factory_of_coulomb_force_calc_fn] and the potential of [factory_of_coulomb_potential_calc_fn].
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_coulomb_force_with_potential_calc_fn::<2, f64>(2.0);
+///
+/// let mut ans_force_and_potential = ([0.0; 2], 0.0);
+/// calc_fn(&[0.0, 0.0], &[0.0, 0.0], 0.0, &[3.0, 4.0], -1.0, &mut ans_force_and_potential);
+///
+/// assert!((ans_force_and_potential.0[0] - 2.0 * -1.0 * -3.0 / 125.0).abs() < 1e-12);
+/// assert!((ans_force_and_potential.0[1] - 2.0 * -1.0 * -4.0 / 125.0).abs() < 1e-12);
+/// assert!((ans_force_and_potential.1 - 2.0 * -1.0 / 5.0).abs() < 1e-12);
+/// ```
+///
+#[allow(clippy::type_complexity)]
+pub fn factory_of_coulomb_force_with_potential_calc_fn<const D: Udim, F: Fnum>(
+    k: F,
+) -> impl Fn(&[F; D], &[F; D], F, &[F; D], F, &mut ([F; D], F)) {
+    move |curr_v_ref: &[F; D],
+          pos_vc_ref: &[F; D],
+          pos_q: F,
+          neg_vc_ref: &[F; D],
+          neg_q: F,
+          ans_mut_ref: &mut ([F; D], F)| {
+        let (field, potential) =
+            calc_coulomb_of_both(curr_v_ref, pos_vc_ref, pos_q, neg_vc_ref, neg_q, k);
+        for (ans_d, field_d) in ans_mut_ref.0.iter_mut().zip(field.iter()) {
+            *ans_d += *field_d;
+        }
+        ans_mut_ref.1 += potential;
+    }
+}
@@ -0,0 +1,338 @@
+//! # Softened Newtonian gravity
+//!
+//! The closures are for [crate::BarnesHutTree::calc_weighted_force_on_value], taking the position of the target value, the center of mass of a super node (or the position of the other value), the total weight (mass), and the answer's mutable reference.
+//!
+//! The force is the one on a target of unit mass, i.e., the acceleration, pointing from the target towards the other mass. Multiply it by the target's mass for the actual force. In the same way, the potential energy is the potential at the target. The displacement is the force times the parameter `step`.
+
+use crate::{Fnum, Udim};
+
+use super::{calc_sum_of_squared, calc_v0_to_v1_diff, DEFAULT_MIN_DIS};
+
+/// Calculate the force scale and the potential of the Plummer softening, the force being the difference times the scale.
+fn calc_plummer<F: Fnum>(dis_pow2: F, g: F, weight: F, eps: F) -> (F, F) {
+    let softened_pow2 = dis_pow2 + eps * eps;
+    let softened_pow2 = if softened_pow2.is_finite() && softened_pow2 > F::from_f64(DEFAULT_MIN_DIS)
+    {
+        softened_pow2
+    } else {
+        F::from_f64(DEFAULT_MIN_DIS)
+    };
+    let inv_dis = F::ONE / softened_pow2.sqrt();
+    let g_weight = g * weight;
+    (g_weight * inv_dis * inv_dis * inv_dis, -g_weight * inv_dis)
+}
+
+/// Calculate the force scale and the potential of the cubic spline softening, the force being the difference times the scale.
+///
+/// The kernel is the one of Monaghan, J. J., & Lattanzio, J. C. (1985). A refined particle method for astrophysical problems. _Astronomy and Astrophysics, 149_, 135-143, in the form used by Springel, V. (2005). The cosmological simulation code GADGET-2. _Monthly Notices of the Royal Astronomical Society, 364_(4), 1105-1134. Beyond the softening length `h`, the gravity is exactly Newtonian.
+fn calc_spline<F: Fnum>(dis_pow2: F, g: F, weight: F, h: F) -> (F, F) {
+    let dis_pow2 = if dis_pow2.is_finite() && dis_pow2 > F::from_f64(DEFAULT_MIN_DIS) {
+        dis_pow2
+    } else {
+        F::from_f64(DEFAULT_MIN_DIS)
+    };
+    let dis = dis_pow2.sqrt();
+    let g_weight = g * weight;
+    if dis >= h {
+        return (g_weight / (dis_pow2 * dis), -g_weight / dis);
+    }
+
+    let u = dis / h;
+    let u_pow2 = u * u;
+    let inv_h = F::ONE / h;
+    let (force_w, potential_w) = if u < F::HALF {
+        (
+            F::from_f64(32.0 / 3.0) + u_pow2 * (F::from_f64(32.0) * u - F::from_f64(38.4)),
+            F::from_f64(-2.8)
+                + u_pow2
+                    * (F::from_f64(16.0 / 3.0)
+                        + u_pow2 * (F::from_f64(6.4) * u - F::from_f64(9.6))),
+        )
+    } else {
+        (
+            F::from_f64(64.0 / 3.0) - F::from_f64(48.0) * u + F::from_f64(38.4) * u_pow2
+                - F::from_f64(32.0 / 3.0) * u_pow2 * u
+                - F::from_f64(1.0 / 15.0) / (u_pow2 * u),
+            F::from_f64(-3.2)
+                + F::from_f64(1.0 / 15.0) / u
+                + u_pow2
+                    * (F::from_f64(32.0 / 3.0)
+                        + u * (F::from_f64(-16.0)
+                            + u * (F::from_f64(9.6) - F::from_f64(32.0 / 15.0) * u))),
+        )
+    };
+    (
+        g_weight * force_w * inv_h * inv_h * inv_h,
+        g_weight * potential_w * inv_h,
+    )
+}
+
+/// This function is the factory of the Newtonian gravity force calculation function with Plummer softening.
+///
+/// The returned closure adds `g * weight * diff / (dis^2 + eps^2)^(3/2)` to the answer, where `diff` is the vector from the target to the other mass.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_plummer_gravity_force_calc_fn::<2, f64>(1.0, 4.0);
+///
+/// let mut ans_force = [0.0; 2];
+/// calc_fn(&[0.0, 0.0], &[3.0, 0.0], 2.0, &mut ans_force);
+///
+/// // The softened distance is `(3^2 + 4^2)^(1/2) = 5`.
+/// assert!((ans_force[0] - 2.0 * 3.0 / 125.0).abs() < 1e-12);
+/// assert_eq!(ans_force[1], 0.0);
+/// ```
+///
+pub fn factory_of_plummer_gravity_force_calc_fn<const D: Udim, F: Fnum>(
+    g: F,
+    eps: F,
+) -> impl Fn(&[F; D], &[F; D], F, &mut [F; D]) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], weight: F, ans_mut_ref: &mut [F; D]| {
+        let diff = calc_v0_to_v1_diff(curr_v_ref, other_vc_ref);
+        let (scalar, _) = calc_plummer(calc_sum_of_squared(&diff), g, weight, eps);
+        for d in 0..D {
+            ans_mut_ref[d] += diff[d] * scalar;
+        }
+    }
+}
+
+/// This function is the factory of the Newtonian gravity displacement calculation function with Plummer softening.
+///
+/// The returned closure adds the force of [factory_of_plummer_gravity_force_calc_fn] times `step` to the answer.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_plummer_gravity_displacement_calc_fn::<2, f64>(1.0, 4.0, 0.5);
+///
+/// let mut ans_displacement = [0.0; 2];
+/// calc_fn(&[0.0, 0.0], &[0.0, -3.0], 2.0, &mut ans_displacement);
+///
+/// assert_eq!(ans_displacement[0], 0.0);
+/// assert!((ans_displacement[1] - 0.5 * 2.0 * -3.0 / 125.0).abs() < 1e-12);
+/// ```
+///
+pub fn factory_of_plummer_gravity_displacement_calc_fn<const D: Udim, F: Fnum>(
+    g: F,
+    eps: F,
+    step: F,
+) -> impl Fn(&[F; D], &[F; D], F, &mut [F; D]) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], weight: F, ans_mut_ref: &mut [F; D]| {
+        let diff = calc_v0_to_v1_diff(curr_v_ref, other_vc_ref);
+        let (scalar, _) = calc_plummer(calc_sum_of_squared(&diff), g, weight, eps);
+        let scalar = scalar * step;
+        for d in 0..D {
+            ans_mut_ref[d] += diff[d] * scalar;
+        }
+    }
+}
+
+/// This function is the factory of the Newtonian gravity potential calculation function with Plummer softening.
+///
+/// The returned closure adds `-g * weight / (dis^2 + eps^2)^(1/2)` to the answer.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_plummer_gravity_potential_calc_fn::<2, f64>(1.0, 4.0);
+///
+/// let mut ans_potential = 0.0;
+/// calc_fn(&[0.0, 0.0], &[3.0, 0.0], 2.0, &mut ans_potential);
+///
+/// assert!((ans_potential - (-2.0 / 5.0)).abs() < 1e-12);
+/// ```
+///
+pub fn factory_of_plummer_gravity_potential_calc_fn<const D: Udim, F: Fnum>(
+    g: F,
+    eps: F,
+) -> impl Fn(&[F; D], &[F; D], F, &mut F) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], weight: F, ans_mut_ref: &mut F| {
+        let diff = calc_v0_to_v1_diff(curr_v_ref, other_vc_ref);
+        let (_, potential) = calc_plummer(calc_sum_of_squared(&diff), g, weight, eps);
+        *ans_mut_ref += potential;
+    }
+}
+
+/// This function is the factory of the Newtonian gravity force and potential calculation function with Plummer softening.
+///
+/// The returned closure updates both the force of [factory_of_plummer_gravity_force_calc_fn] and the potential of [factory_of_plummer_gravity_potential_calc_fn].
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_plummer_gravity_force_with_potential_calc_fn::<2, f64>(1.0, 4.0);
+///
+/// let mut ans_force_and_potential = ([0.0; 2], 0.0);
+/// calc_fn(&[0.0, 0.0], &[3.0, 0.0], 2.0, &mut ans_force_and_potential);
+///
+/// assert!((ans_force_and_potential.0[0] - 2.0 * 3.0 / 125.0).abs() < 1e-12);
+/// assert!((ans_force_and_potential.1 - (-2.0 / 5.0)).abs() < 1e-12);
+/// ```
+///
+#[allow(clippy::type_complexity)]
+pub fn factory_of_plummer_gravity_force_with_potential_calc_fn<const D: Udim, F: Fnum>(
+    g: F,
+    eps: F,
+) -> impl Fn(&[F; D], &[F; D], F, &mut ([F; D], F)) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], weight: F, ans_mut_ref: &mut ([F; D], F)| {
+        let diff = calc_v0_to_v1_diff(curr_v_ref, other_vc_ref);
+        let (scalar, potential) = calc_plummer(calc_sum_of_squared(&diff), g, weight, eps);
+        for (ans_d, diff_d) in ans_mut_ref.0.iter_mut().zip(diff.iter()) {
+            *ans_d += *diff_d * scalar;
+        }
+        ans_mut_ref.1 += potential;
+    }
+}
+
+/// This function is the factory of the Newtonian gravity force calculation function with cubic spline softening.
+///
+/// Within the softening length `h`, the mass is spread by the cubic spline kernel of Monaghan and Lattanzio (1985), and the force goes smoothly to zero at zero distance. Beyond `h`, the returned closure adds the exact Newtonian force `g * weight * diff / dis^3` to the answer, where `diff` is the vector from the target to the other mass.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_spline_gravity_force_calc_fn::<2, f64>(1.0, 2.0);
+///
+/// // Beyond the softening length, the force is Newtonian.
+/// let mut ans_force = [0.0; 2];
+/// calc_fn(&[0.0, 0.0], &[4.0, 0.0], 2.0, &mut ans_force);
+/// assert!((ans_force[0] - 2.0 / 16.0).abs() < 1e-12);
+///
+/// // Within the softening length, `u = dis / h = 0.25`.
+/// let u: f64 = 0.25;
+/// let mut ans_force = [0.0; 2];
+/// calc_fn(&[0.0, 0.0], &[0.5, 0.0], 2.0, &mut ans_force);
+/// let expected_scale = 2.0 * (32.0 / 3.0 - 192.0 / 5.0 * u.powi(2) + 32.0 * u.powi(3)) / 2.0_f64.powi(3);
+/// assert!((ans_force[0] - 0.5 * expected_scale).abs() < 1e-12);
+/// ```
+///
+pub fn factory_of_spline_gravity_force_calc_fn<const D: Udim, F: Fnum>(
+    g: F,
+    h: F,
+) -> impl Fn(&[F; D], &[F; D], F, &mut [F; D]) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], weight: F, ans_mut_ref: &mut [F; D]| {
+        let diff = calc_v0_to_v1_diff(curr_v_ref, other_vc_ref);
+        let (scalar, _) = calc_spline(calc_sum_of_squared(&diff), g, weight, h);
+        for d in 0..D {
+            ans_mut_ref[d] += diff[d] * scalar;
+        }
+    }
+}
+
+/// This function is the factory of the Newtonian gravity displacement calculation function with cubic spline softening.
+///
+/// The returned closure adds the force of [factory_of_spline_gravity_force_calc_fn] times `step` to the answer.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_spline_gravity_displacement_calc_fn::<2, f64>(1.0, 2.0, 0.5);
+///
+/// let mut ans_displacement = [0.0; 2];
+/// calc_fn(&[0.0, 0.0], &[0.0, -4.0], 2.0, &mut ans_displacement);
+///
+/// assert!((ans_displacement[1] - 0.5 * -2.0 / 16.0).abs() < 1e-12);
+/// ```
+///
+pub fn factory_of_spline_gravity_displacement_calc_fn<const D: Udim, F: Fnum>(
+    g: F,
+    h: F,
+    step: F,
+) -> impl Fn(&[F; D], &[F; D], F, &mut [F; D]) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], weight: F, ans_mut_ref: &mut [F; D]| {
+        let diff = calc_v0_to_v1_diff(curr_v_ref, other_vc_ref);
+        let (scalar, _) = calc_spline(calc_sum_of_squared(&diff), g, weight, h);
+        let scalar = scalar * step;
+        for d in 0..D {
+            ans_mut_ref[d] += diff[d] * scalar;
+        }
+    }
+}
+
+/// This function is the factory of the Newtonian gravity potential calculation function with cubic spline softening.
+///
+/// Beyond the softening length `h`, the returned closure adds the exact Newtonian potential `-g * weight / dis` to the answer. At zero distance, the potential is `-2.8 * g * weight / h`.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_spline_gravity_potential_calc_fn::<2, f64>(1.0, 2.0);
+///
+/// let mut ans_potential = 0.0;
+/// calc_fn(&[0.0, 0.0], &[4.0, 0.0], 2.0, &mut ans_potential);
+/// assert!((ans_potential - (-2.0 / 4.0)).abs() < 1e-12);
+///
+/// // Within the softening length, `u = dis / h = 0.25`.
+/// let u: f64 = 0.25;
+/// let mut ans_potential = 0.0;
+/// calc_fn(&[0.0, 0.0], &[0.5, 0.0], 2.0, &mut ans_potential);
+/// let expected_potential = 2.0 * (16.0 / 3.0 * u.powi(2) - 48.0 / 5.0 * u.powi(4) + 32.0 / 5.0 * u.powi(5) - 14.0 / 5.0) / 2.0;
+/// assert!((ans_potential - expected_potential).abs() < 1e-12);
+/// ```
+///
+pub fn factory_of_spline_gravity_potential_calc_fn<const D: Udim, F: Fnum>(
+    g: F,
+    h: F,
+) -> impl Fn(&[F; D], &[F; D], F, &mut F) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], weight: F, ans_mut_ref: &mut F| {
+        let diff = calc_v0_to_v1_diff(curr_v_ref, other_vc_ref);
+        let (_, potential) = calc_spline(calc_sum_of_squared(&diff), g, weight, h);
+        *ans_mut_ref += potential;
+    }
+}
+
+/// This function is the factory of the Newtonian gravity force and potential calculation function with cubic spline softening.
+///
+/// The returned closure updates both the force of [factory_of_spline_gravity_force_calc_fn] and the potential of [factory_of_spline_gravity_potential_calc_fn].
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::factory_of_spline_gravity_force_with_potential_calc_fn::<2, f64>(1.0, 2.0);
+///
+/// // Within the softening length, `u = dis / h = 0.75`.
+/// let u: f64 = 0.75;
+/// let mut ans_force_and_potential = ([0.0; 2], 0.0);
+/// calc_fn(&[0.0, 0.0], &[1.5, 0.0], 2.0, &mut ans_force_and_potential);
+///
+/// let expected_scale = 2.0
+///     * (64.0 / 3.0 - 48.0 * u + 192.0 / 5.0 * u.powi(2) - 32.0 / 3.0 * u.powi(3) - 1.0 / (15.0 * u.powi(3)))
+///     / 2.0_f64.powi(3);
+/// let expected_potential = 2.0
+///     * (1.0 / (15.0 * u) + 32.0 / 3.0 * u.powi(2) - 16.0 * u.powi(3) + 48.0 / 5.0 * u.powi(4) - 32.0 / 15.0 * u.powi(5) - 16.0 / 5.0)
+///     / 2.0;
+/// assert!((ans_force_and_potential.0[0] - 1.5 * expected_scale).abs() < 1e-12);
+/// assert!((ans_force_and_potential.1 - expected_potential).abs() < 1e-12);
+/// ```
+///
+#[allow(clippy::type_complexity)]
+pub fn factory_of_spline_gravity_force_with_potential_calc_fn<const D: Udim, F: Fnum>(
+    g: F,
+    h: F,
+) -> impl Fn(&[F; D], &[F; D], F, &mut ([F; D], F)) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], weight: F, ans_mut_ref: &mut ([F; D], F)| {
+        let diff = calc_v0_to_v1_diff(curr_v_ref, other_vc_ref);
+        let (scalar, potential) = calc_spline(calc_sum_of_squared(&diff), g, weight, h);
+        for (ans_d, diff_d) in ans_mut_ref.0.iter_mut().zip(diff.iter()) {
+            *ans_d += *diff_d * scalar;
+        }
+        ans_mut_ref.1 += potential;
+    }
+}
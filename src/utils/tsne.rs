@@ -0,0 +1,119 @@
+//! # Barnes-Hut t-SNE
+//!
+//! The helpers calculate the gradient of t-SNE in the way of van der Maaten, L. (2014). Accelerating t-SNE using tree-based algorithms. _The Journal of Machine Learning Research, 15_(1), 3221-3245.
+//!
+//! With the Student-t kernel `q = 1 / (1 + dis^2)`, the gradient on an embedded value `y_i` is `4 * (sum(p_ij * q_ij * (y_i - y_j)) - sum(q_ij^2 * (y_i - y_j)) / Z)`, where `Z` is the sum of `q` over all the ordered pairs of values. The first, attractive term only goes through the sparse input similarities, while the second, repulsive term and `Z` go through all the pairs, which is where the tree helps.
+
+use crate::{BarnesHutTree, Fnum, OpeningCriterion, Udim};
+
+use super::{calc_sum_of_squared, calc_v0_to_v1_diff};
+
+/// This function is the factory of the t-SNE repulsive term calculation function.
+///
+/// The returned closure takes the position of the target value, the mean position of a group of values, the size of the group, and the answer's mutable reference. It adds `num * q^2 * (y_i - y_c)` to the unnormalized repulsive displacement and `num * q` to the sum of `q`, where `q = 1 / (1 + dis^2)`.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// let calc_fn = zbht::utils::tsne::factory_of_repulsive_calc_fn::<2, f64>();
+///
+/// let mut ans_displacement_and_z_sum = ([0.0; 2], 0.0);
+/// calc_fn(&[0.0, 0.0], &[1.0, 0.0], 2, &mut ans_displacement_and_z_sum);
+///
+/// // The kernel `q = 1 / (1 + 1) = 0.5`.
+/// assert_eq!(ans_displacement_and_z_sum, ([2.0 * 0.25 * -1.0, 0.0], 2.0 * 0.5));
+/// ```
+///
+#[allow(clippy::type_complexity)]
+pub fn factory_of_repulsive_calc_fn<const D: Udim, F: Fnum>(
+) -> impl Fn(&[F; D], &[F; D], usize, &mut ([F; D], F)) {
+    move |curr_v_ref: &[F; D], other_vc_ref: &[F; D], num: usize, ans_mut_ref: &mut ([F; D], F)| {
+        let diff = calc_v0_to_v1_diff(other_vc_ref, curr_v_ref);
+        let q = F::ONE / (F::ONE + calc_sum_of_squared(&diff));
+        let num_q = F::from_usize(num) * q;
+        ans_mut_ref.1 += num_q;
+        let scalar = num_q * q;
+        for (ans_d, diff_d) in ans_mut_ref.0.iter_mut().zip(diff.iter()) {
+            *ans_d += *diff_d * scalar;
+        }
+    }
+}
+
+/// Calculate one full pass of the t-SNE gradient on all the values in the tree.
+///
+/// The input similarities `p_pairs` are the sparse symmetric joint probabilities `(i, j, p_ij)`, listing each unordered pair of values once, and each entry acts on both values. The repulsive term is calculated by [crate::BarnesHutTree::calc_force_on_all_values] with the kernel of [factory_of_repulsive_calc_fn].
+///
+/// The gradient is added to the values' slots of `write_to`, which should have one slot for each value. To descend, subtract the gradient times the learning rate from the positions.
+///
+/// ## Return
+///
+/// This function returns the normalization `Z`, or `None` without calculating anything if the length of `write_to` is not the number of values or an index in `p_pairs` is out of range.
+///
+/// ## Example
+///
+/// ```rust
+/// use zhifeng_impl_barnes_hut_tree as zbht;
+///
+/// use zbht::BarnesHutTree as BHTree;
+///
+/// let bht: BHTree<2> = BHTree::from_values(&[[0.0, 0.0], [1.0, 0.0]]);
+///
+/// let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(0.5);
+///
+/// let mut ans_gradients = vec![[0.0; 2]; 2];
+/// let z = zbht::utils::tsne::calc_gradient(&bht, &is_super_fn, &[(0, 1, 0.5)], &mut ans_gradients);
+///
+/// // Both ordered pairs have `q = 0.5`, so `Z = 1`, and `q_ij = 0.5` equals `p_ij`.
+/// assert_eq!(z, Some(1.0));
+/// assert_eq!(ans_gradients, vec![[0.0, 0.0], [0.0, 0.0]]);
+///
+/// assert_eq!(zbht::utils::tsne::calc_gradient(&bht, &is_super_fn, &[(0, 2, 0.5)], &mut ans_gradients), None);
+/// ```
+///
+pub fn calc_gradient<const D: Udim, F: Fnum>(
+    bht: &BarnesHutTree<D, F>,
+    is_super_node: impl OpeningCriterion<D, F>,
+    p_pairs: &[(usize, usize, F)],
+    write_to: &mut [[F; D]],
+) -> Option<F> {
+    let len = write_to.len();
+    if p_pairs.iter().any(|(i, j, _)| *i >= len || *j >= len) {
+        return None;
+    }
+
+    let mut repulsions = vec![([F::ZERO; D], F::ZERO); len];
+    if !bht.calc_force_on_all_values(
+        is_super_node,
+        factory_of_repulsive_calc_fn(),
+        &mut repulsions,
+    ) {
+        return None;
+    }
+    let mut z = F::ZERO;
+    for (_, z_sum) in repulsions.iter() {
+        z += *z_sum;
+    }
+
+    // With less than two values, there is nothing to repel.
+    if z > F::ZERO {
+        let inv_z = F::ONE / z;
+        for (curr_write_to, (repulsion, _)) in write_to.iter_mut().zip(repulsions.iter()) {
+            for d in 0..D {
+                curr_write_to[d] -= F::from_f64(4.0) * repulsion[d] * inv_z;
+            }
+        }
+    }
+
+    for (i, j, p) in p_pairs.iter().cloned() {
+        let (v_i, v_j) = (bht.get(i)?, bht.get(j)?);
+        let diff = calc_v0_to_v1_diff(v_j, v_i);
+        let scalar = F::from_f64(4.0) * p / (F::ONE + calc_sum_of_squared(&diff));
+        for d in 0..D {
+            write_to[i][d] += diff[d] * scalar;
+            write_to[j][d] -= diff[d] * scalar;
+        }
+    }
+    Some(z)
+}
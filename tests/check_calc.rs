@@ -25,6 +25,31 @@ fn check_calc_on_tree_with_one_internal_insertion() -> Result<(), Box<dyn std::e
     Ok(())
 }
 
+#[test]
+fn check_exact_calc_on_values_sharing_a_leaf_counts_one() -> Result<(), Box<dyn std::error::Error>>
+{
+    const D: usize = 2;
+    // The last three values share one leaf at the radius limit.
+    let vals: Vec<[f64; D]> = vec![[-3.0, -3.0], [1.0, 1.0], [1.2, 1.0], [1.0, 1.2]];
+
+    let bht: BHTree<D> = BHTree::with_bounding_and_values_and_limit(&[0.0, 0.0], 4.0, &vals, 1.0);
+    assert_eq!(bht.get_total_nodes_num(), 4);
+
+    let is_super_fn = |_: &[Fnum; D], _: &[Fnum; D], _: Fnum| -> bool { false };
+    let calc_fn = |_: &[Fnum; D], _: &[Fnum; D], num: usize, ans: &mut Vec<usize>| ans.push(num);
+
+    // The other leaf is not far enough, so its values are calculated one by one.
+    let mut nums = Vec::new();
+    bht.calc_force_on_value(0, is_super_fn, calc_fn, &mut nums);
+    assert_eq!(nums, vec![1, 1, 1]);
+
+    // So are the other values in the target's own leaf.
+    let mut nums = Vec::new();
+    bht.calc_force_on_value(1, is_super_fn, calc_fn, &mut nums);
+    assert_eq!(nums, vec![1, 1, 1]);
+    Ok(())
+}
+
 fn generate_random_values<const D: Udim>(len: usize, ranges: &[Range<Fnum>; D]) -> Vec<[Fnum; D]> {
     let mut ans_vec: Vec<[Fnum; D]> = Vec::with_capacity(len);
     let mut rng = rand::thread_rng();
@@ -104,6 +129,7 @@ fn check_exact_calc_on_100_random_values() -> Result<(), Box<dyn std::error::Err
 }
 
 #[test]
+#[allow(clippy::needless_range_loop)]
 fn check_exact_force_simulation_on_100_random_values_with_energy(
 ) -> Result<(), Box<dyn std::error::Error>> {
     const D: usize = 2;
@@ -136,7 +162,7 @@ fn check_exact_force_simulation_on_100_random_values_with_energy(
 
         assert_values_and_energy_close(&displacement, &expected_displacement, 1e-9);
 
-        let mut new_value = values[value_i].clone();
+        let mut new_value = values[value_i];
         for d in 0..D {
             new_value[d] += displacement.0[d];
         }
@@ -179,7 +205,7 @@ fn check_exact_force_simulation_on_1000_random_values() -> Result<(), Box<dyn st
 
         assert_values_close(&displacement, &expected_displacement, 1e-9);
 
-        let mut new_value = values[value_i].clone();
+        let mut new_value = values[value_i];
         for d in 0..D {
             new_value[d] += displacement[d];
         }
@@ -192,6 +218,7 @@ fn check_exact_force_simulation_on_1000_random_values() -> Result<(), Box<dyn st
 
 /// This test is about the randomly removing values out from the `BHTree` during the simulation process.
 #[test]
+#[allow(clippy::needless_range_loop)]
 fn check_exact_force_simulation_and_all_remove_on_750_random_values(
 ) -> Result<(), Box<dyn std::error::Error>> {
     const D: usize = 2;
@@ -211,9 +238,7 @@ fn check_exact_force_simulation_and_all_remove_on_750_random_values(
     for i in 0..len {
         let len_remain = len - i;
         let rand_i = i + rand::random::<usize>() % len_remain;
-        let temp = idxs[i];
-        idxs[i] = idxs[rand_i];
-        idxs[rand_i] = temp;
+        idxs.swap(i, rand_i);
     }
     let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
 
@@ -247,7 +272,7 @@ fn check_exact_force_simulation_and_all_remove_on_750_random_values(
 
             assert_values_close(&displacement, &expected_displacement, 1e-9);
 
-            let mut new_value = values[value_i].clone();
+            let mut new_value = values[value_i];
             for d in 0..D {
                 new_value[d] += displacement[d];
             }
@@ -272,3 +297,1164 @@ fn check_exact_force_simulation_and_all_remove_on_750_random_values(
     );
     Ok(())
 }
+
+#[test]
+fn check_exact_charged_calc_on_100_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 100;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let charges: Vec<Fnum> = generate_random_values::<1>(len, &[-1.0..1.0])
+        .into_iter()
+        .map(|q| q[0])
+        .collect();
+    let mut bht: BHTree<2> = BHTree::with_bounding_and_capacity(&[0.0, 0.0], 5.0, len);
+    for (value, charge) in values.iter().zip(charges.iter()) {
+        bht.push_charged(value, *charge);
+    }
+
+    let coulomb_fn = |curr_v: &[Fnum; D], other_v: &[Fnum; D], q: Fnum, ans: &mut [Fnum; D]| {
+        let mut dis_pow2 = 0.0;
+        for d in 0..D {
+            dis_pow2 += (curr_v[d] - other_v[d]).powi(2);
+        }
+        let scalar = q / dis_pow2.powf(1.5);
+        for d in 0..D {
+            ans[d] += (curr_v[d] - other_v[d]) * scalar;
+        }
+    };
+
+    for value_i in 0..len {
+        let mut field = [0.0; D];
+        let mut expected_field = [0.0; D];
+        bht.calc_charged_force_on_value(
+            value_i,
+            zbht::utils::criterion_fn(|_, _, _| -> bool { false }),
+            |curr_v, pos_vc, pos_q, neg_vc, neg_q, ans: &mut [Fnum; D]| {
+                if pos_q > 0.0 {
+                    coulomb_fn(curr_v, pos_vc, pos_q, ans);
+                }
+                if neg_q < 0.0 {
+                    coulomb_fn(curr_v, neg_vc, neg_q, ans);
+                }
+            },
+            &mut field,
+        );
+
+        for value_j in 0..len {
+            if value_j == value_i {
+                continue;
+            }
+            coulomb_fn(
+                &values[value_i],
+                &values[value_j],
+                charges[value_j],
+                &mut expected_field,
+            );
+        }
+
+        assert_values_close(&field, &expected_field, 1e-9);
+    }
+    Ok(())
+}
+
+#[test]
+fn check_f32_calc_close_to_f64_calc_on_100_random_values() -> Result<(), Box<dyn std::error::Error>>
+{
+    const D: usize = 2;
+    let len = 100;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let values_f32: Vec<[f32; D]> = values.iter().map(|v| [v[0] as f32, v[1] as f32]).collect();
+    let values: Vec<[Fnum; D]> = values_f32
+        .iter()
+        .map(|v| [v[0] as Fnum, v[1] as Fnum])
+        .collect();
+
+    let bht: BHTree<D> = BHTree::with_bounding_and_values(&[0.0, 0.0], 5.0, &values);
+    let bht_f32: BHTree<D, f32> = BHTree::with_bounding_and_values(&[0.0, 0.0], 5.0, &values_f32);
+
+    let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(1.2);
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    let is_super_fn_f32 = zbht::utils::factory_of_is_super_node_fn_of::<2, f32>(1.2);
+    let calc_fn_f32 = zbht::utils::factory_of_repulsive_displacement_calc_fn_of::<2, f32>(1.0, 0.2);
+
+    for value_i in 0..len {
+        let mut displacement = [0.0; D];
+        let mut displacement_f32 = [0.0; D];
+        bht.calc_force_on_value(value_i, &is_super_fn, &calc_fn, &mut displacement);
+        bht_f32.calc_force_on_value(
+            value_i,
+            &is_super_fn_f32,
+            &calc_fn_f32,
+            &mut displacement_f32,
+        );
+
+        assert_values_close(
+            &[displacement_f32[0] as Fnum, displacement_f32[1] as Fnum],
+            &displacement,
+            1e-3,
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn check_calc_on_all_values_same_as_on_each_value() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(1.2);
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+
+    let mut displacements = vec![[0.0; D]; len];
+    assert!(bht.calc_force_on_all_values(&is_super_fn, &calc_fn, &mut displacements));
+
+    for (value_i, displacement) in displacements.iter().enumerate() {
+        let mut expected_displacement = [0.0; D];
+        bht.calc_force_on_value(value_i, &is_super_fn, &calc_fn, &mut expected_displacement);
+        assert_eq!(displacement, &expected_displacement);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn check_parallel_calc_on_all_values_same_as_sequential() -> Result<(), Box<dyn std::error::Error>>
+{
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(1.2);
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_with_energy_calc_fn::<2>(1.0, 0.2);
+
+    let mut ans = vec![([0.0; D], 0.0); len];
+    assert!(bht.calc_force_on_all_values_in_parallel(&is_super_fn, &calc_fn, &mut ans));
+
+    let mut expected_ans = vec![([0.0; D], 0.0); len];
+    assert!(bht.calc_force_on_all_values(&is_super_fn, &calc_fn, &mut expected_ans));
+
+    assert_eq!(ans, expected_ans);
+    Ok(())
+}
+
+#[test]
+fn check_calc_at_point_on_100_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 100;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let points = generate_random_values(len, &[-12.0..12.0, -12.0..12.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(1.2);
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+
+    for point in points.iter() {
+        let mut displacement = [0.0; D];
+        let mut expected_displacement = [0.0; D];
+        assert!(bht.calc_force_at_point(
+            point,
+            None,
+            zbht::utils::criterion_fn(|_, _, _| -> bool { false }),
+            &calc_fn,
+            &mut displacement,
+        ));
+        for value in values.iter() {
+            calc_fn(point, value, 1, &mut expected_displacement);
+        }
+        assert_values_close(&displacement, &expected_displacement, 1e-9);
+    }
+
+    for (value_i, value) in values.iter().enumerate() {
+        let mut displacement = [0.0; D];
+        let mut expected_displacement = [0.0; D];
+        assert!(bht.calc_force_at_point(
+            value,
+            Some(value_i),
+            &is_super_fn,
+            &calc_fn,
+            &mut displacement,
+        ));
+        bht.calc_force_on_value(value_i, &is_super_fn, &calc_fn, &mut expected_displacement);
+        assert_values_close(&displacement, &expected_displacement, 1e-9);
+    }
+    Ok(())
+}
+
+#[test]
+fn check_dual_tree_calc_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    let merge_fn = |acc: &mut [Fnum; D], other: &[Fnum; D]| {
+        for d in 0..D {
+            acc[d] += other[d];
+        }
+    };
+
+    let mut exact_ans = vec![[0.0; D]; len];
+    assert!(bht.calc_forces_dual_tree(
+        |_, _, _, _| -> bool { false },
+        &calc_fn,
+        merge_fn,
+        [0.0; D],
+        &mut exact_ans,
+    ));
+    for value_i in 0..len {
+        let mut expected_displacement = [0.0; D];
+        for value_j in 0..len {
+            if value_j != value_i {
+                calc_fn(
+                    &values[value_i],
+                    &values[value_j],
+                    1,
+                    &mut expected_displacement,
+                );
+            }
+        }
+        assert_values_close(&exact_ans[value_i], &expected_displacement, 1e-9);
+    }
+
+    let is_well_separated_fn = zbht::utils::factory_of_is_well_separated_fn::<2, f64>(0.5);
+    let mut ans = vec![[0.0; D]; len];
+    assert!(bht.calc_forces_dual_tree(
+        &is_well_separated_fn,
+        &calc_fn,
+        merge_fn,
+        [0.0; D],
+        &mut ans,
+    ));
+    let (mut err_pow2, mut exact_pow2) = (0.0, 0.0);
+    for (displacement, exact_displacement) in ans.iter().zip(exact_ans.iter()) {
+        for d in 0..D {
+            err_pow2 += (displacement[d] - exact_displacement[d]).powi(2);
+            exact_pow2 += exact_displacement[d].powi(2);
+        }
+    }
+    // The values in an accepted node share one result, which puts the error at about 3% here.
+    let rel_err = (err_pow2 / exact_pow2).sqrt();
+    assert!(
+        rel_err < 0.05,
+        "Relative error of the dual-tree calculation: {rel_err}"
+    );
+    Ok(())
+}
+
+#[test]
+fn check_symmetric_dual_tree_calc_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>>
+{
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    let kernel = zbht::PairKernel(zbht::utils::factory_of_repulsive_displacement_pair_fn::<
+        2,
+        f64,
+    >(1.0, 0.2));
+    let merge_fn = |acc: &mut [Fnum; D], other: &[Fnum; D]| {
+        for d in 0..D {
+            acc[d] += other[d];
+        }
+    };
+
+    let mut exact_ans = vec![[0.0; D]; len];
+    assert!(bht.calc_symmetric_forces_dual_tree(
+        |_, _, _, _| -> bool { false },
+        &kernel,
+        merge_fn,
+        [0.0; D],
+        &mut exact_ans,
+    ));
+    let mut total = [0.0; D];
+    for value_i in 0..len {
+        let mut expected_displacement = [0.0; D];
+        for value_j in 0..len {
+            if value_j != value_i {
+                calc_fn(
+                    &values[value_i],
+                    &values[value_j],
+                    1,
+                    &mut expected_displacement,
+                );
+            }
+        }
+        assert_values_close(&exact_ans[value_i], &expected_displacement, 1e-9);
+        merge_fn(&mut total, &exact_ans[value_i]);
+    }
+    assert_values_close(&total, &[0.0; D], 1e-9);
+
+    let is_well_separated_fn = zbht::utils::factory_of_is_well_separated_fn::<2, f64>(0.5);
+    let mut ans = vec![[0.0; D]; len];
+    assert!(bht.calc_symmetric_forces_dual_tree(
+        &is_well_separated_fn,
+        &kernel,
+        merge_fn,
+        [0.0; D],
+        &mut ans,
+    ));
+    let mut expected_ans = vec![[0.0; D]; len];
+    assert!(bht.calc_forces_dual_tree(
+        &is_well_separated_fn,
+        &calc_fn,
+        merge_fn,
+        [0.0; D],
+        &mut expected_ans,
+    ));
+    for (displacement, expected_displacement) in ans.iter().zip(expected_ans.iter()) {
+        assert_values_close(displacement, expected_displacement, 1e-9);
+    }
+    Ok(())
+}
+
+/// A symmetric kernel with the repulsive displacement and the number of exact values on each value.
+struct CountingPairKernel {
+    k: Fnum,
+    c: Fnum,
+    pairs_num: std::cell::Cell<usize>,
+}
+
+impl zbht::Kernel<2, Fnum, ([Fnum; 2], usize)> for CountingPairKernel {
+    fn exact(
+        &self,
+        curr_v_ref: &[Fnum; 2],
+        _: usize,
+        other_v_ref: &[Fnum; 2],
+        write_to: &mut ([Fnum; 2], usize),
+    ) {
+        zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(self.k, self.c)(
+            curr_v_ref,
+            other_v_ref,
+            1,
+            &mut write_to.0,
+        );
+        write_to.1 += 1;
+    }
+
+    fn approx(
+        &self,
+        curr_v_ref: &[Fnum; 2],
+        node: &zbht::NodeSummary<'_, 2, Fnum>,
+        write_to: &mut ([Fnum; 2], usize),
+    ) {
+        zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(self.k, self.c)(
+            curr_v_ref,
+            node.vc,
+            node.count,
+            &mut write_to.0,
+        );
+    }
+}
+
+impl zbht::SymmetricKernel<2, Fnum, ([Fnum; 2], usize)> for CountingPairKernel {
+    fn exact_pair(
+        &self,
+        _: usize,
+        v0_ref: &[Fnum; 2],
+        _: usize,
+        v1_ref: &[Fnum; 2],
+        write_to_0: &mut ([Fnum; 2], usize),
+        write_to_1: &mut ([Fnum; 2], usize),
+    ) {
+        let displacement = zbht::utils::factory_of_repulsive_displacement_pair_fn::<2, f64>(
+            self.k, self.c,
+        )(v0_ref, v1_ref);
+        for (d, displacement_d) in displacement.iter().enumerate() {
+            write_to_0.0[d] += displacement_d;
+            write_to_1.0[d] -= displacement_d;
+        }
+        write_to_0.1 += 1;
+        write_to_1.1 += 1;
+        self.pairs_num.set(self.pairs_num.get() + 1);
+    }
+}
+
+#[test]
+fn check_symmetric_dual_tree_calc_with_custom_result_on_100_random_values(
+) -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 100;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let kernel = CountingPairKernel {
+        k: 1.0,
+        c: 0.2,
+        pairs_num: std::cell::Cell::new(0),
+    };
+    let merge_fn = |acc: &mut ([Fnum; D], usize), other: &([Fnum; D], usize)| {
+        for d in 0..D {
+            acc.0[d] += other.0[d];
+        }
+        acc.1 += other.1;
+    };
+
+    let mut ans = vec![([0.0; D], 0); len];
+    assert!(bht.calc_symmetric_forces_dual_tree(
+        |_, _, _, _| -> bool { false },
+        &kernel,
+        merge_fn,
+        ([0.0; D], 0),
+        &mut ans,
+    ));
+    // Each pair of values is calculated once.
+    assert_eq!(kernel.pairs_num.get(), len * (len - 1) / 2);
+
+    let mut expected_ans = vec![([0.0; D], 0); len];
+    assert!(bht.calc_forces_dual_tree(
+        |_, _, _, _| -> bool { false },
+        kernel,
+        merge_fn,
+        ([0.0; D], 0),
+        &mut expected_ans,
+    ));
+    for (curr_ans, expected_curr_ans) in ans.iter().zip(expected_ans.iter()) {
+        assert_values_close(&curr_ans.0, &expected_curr_ans.0, 1e-9);
+        assert_eq!(curr_ans.1, len - 1);
+        assert_eq!(expected_curr_ans.1, len - 1);
+    }
+    Ok(())
+}
+
+#[test]
+fn check_opening_criteria_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+
+    let mut exact_ans = vec![[0.0; D]; len];
+    assert!(bht.calc_force_on_all_values(
+        zbht::utils::criterion_fn(|_, _, _| -> bool { false }),
+        &calc_fn,
+        &mut exact_ans
+    ));
+    let calc_rel_err = |ans: &[[Fnum; D]]| -> Fnum {
+        let (mut err_pow2, mut exact_pow2) = (0.0, 0.0);
+        for (displacement, exact_displacement) in ans.iter().zip(exact_ans.iter()) {
+            for d in 0..D {
+                err_pow2 += (displacement[d] - exact_displacement[d]).powi(2);
+                exact_pow2 += exact_displacement[d].powi(2);
+            }
+        }
+        (err_pow2 / exact_pow2).sqrt()
+    };
+
+    let mut ans = vec![[0.0; D]; len];
+    assert!(bht.calc_force_on_all_values(
+        zbht::utils::factory_of_barnes_hut_criterion::<2, f64>(0.5),
+        &calc_fn,
+        &mut ans,
+    ));
+    let mut expected_ans = vec![[0.0; D]; len];
+    assert!(bht.calc_force_on_all_values(
+        zbht::utils::factory_of_is_super_node_fn::<2>(0.5),
+        &calc_fn,
+        &mut expected_ans,
+    ));
+    assert_eq!(ans, expected_ans);
+
+    let mut ans = vec![[0.0; D]; len];
+    assert!(bht.calc_force_on_all_values(
+        zbht::utils::factory_of_min_distance_criterion::<2, f64>(0.5),
+        &calc_fn,
+        &mut ans,
+    ));
+    let rel_err = calc_rel_err(&ans);
+    assert!(rel_err < 0.02, "Relative error of min-distance: {rel_err}");
+
+    #[cfg(feature = "quadrupole")]
+    {
+        // The second moment of the root is the sum over all the values.
+        let root_summary = bht.root().expect("The tree should have a root").summary();
+        let mut expected_b2 = 0.0;
+        for value in values.iter() {
+            for (v_d, vc_d) in value.iter().zip(root_summary.vc.iter()) {
+                expected_b2 += (v_d - vc_d).powi(2);
+            }
+        }
+        let b2 = root_summary
+            .b2
+            .expect("The second moment should be tracked");
+        assert!((b2 - expected_b2).abs() < 1e-9 * expected_b2);
+
+        let mut ans = vec![[0.0; D]; len];
+        assert!(bht.calc_force_on_all_values(
+            zbht::utils::factory_of_salmon_warren_criterion::<2, f64>(0.1),
+            &calc_fn,
+            &mut ans,
+        ));
+        let rel_err = calc_rel_err(&ans);
+        assert!(rel_err < 0.02, "Relative error of Salmon-Warren: {rel_err}");
+    }
+
+    let criterion = zbht::utils::factory_of_relative_criterion::<2, f64>(0.05, 1.0, 0.2);
+    let mut ans = vec![[0.0; D]; len];
+    assert!(bht.calc_force_on_all_values(criterion, &calc_fn, &mut ans));
+    let rel_err = calc_rel_err(&ans);
+    assert!(rel_err < 0.02, "Relative error of relative: {rel_err}");
+    Ok(())
+}
+
+struct CountingKernel;
+
+impl zbht::Kernel<2, Fnum, (usize, usize, usize)> for CountingKernel {
+    fn exact(
+        &self,
+        _: &[Fnum; 2],
+        other_i: usize,
+        _: &[Fnum; 2],
+        write_to: &mut (usize, usize, usize),
+    ) {
+        write_to.0 += 1;
+        write_to.2 += other_i;
+    }
+
+    fn approx(
+        &self,
+        _: &[Fnum; 2],
+        node: &zbht::NodeSummary<'_, 2, Fnum>,
+        write_to: &mut (usize, usize, usize),
+    ) {
+        write_to.1 += node.count;
+    }
+}
+
+#[test]
+fn check_kernel_exact_and_approx_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+    let indices_sum: usize = (0..len).sum();
+
+    let mut ans = vec![(0, 0, 0); len];
+    assert!(bht.calc_force_on_all_values(
+        zbht::utils::criterion_fn(|_, _, _| -> bool { false }),
+        CountingKernel,
+        &mut ans,
+    ));
+    for (value_i, counts) in ans.iter().enumerate() {
+        assert_eq!(*counts, (len - 1, 0, indices_sum - value_i));
+    }
+
+    let mut ans = vec![(0, 0, 0); len];
+    assert!(bht.calc_force_on_all_values(
+        zbht::utils::factory_of_is_super_node_fn::<2>(1.2),
+        CountingKernel,
+        &mut ans,
+    ));
+    let mut approx_num = 0;
+    for counts in ans.iter() {
+        assert_eq!(counts.0 + counts.1, len - 1);
+        approx_num += counts.1;
+    }
+    assert!(approx_num > 0);
+    Ok(())
+}
+
+#[test]
+fn check_calc_with_indices_skipping_pairs_on_100_random_values(
+) -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 100;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+    let is_connected = |value_i: usize, value_j: usize| (value_i + value_j).is_multiple_of(7);
+
+    for value_i in 0..len {
+        let mut displacement = [0.0; D];
+        assert!(bht.calc_force_on_value_with_indices(
+            value_i,
+            zbht::utils::criterion_fn(|_, _, _| -> bool { false }),
+            |target_i: usize,
+             curr_v_ref: &[Fnum; D],
+             other_i: usize,
+             other_v_ref: &[Fnum; D],
+             write_to: &mut [Fnum; D]| {
+                assert_eq!(target_i, value_i);
+                assert_eq!(curr_v_ref, &values[target_i]);
+                assert_eq!(other_v_ref, &values[other_i]);
+                if !is_connected(target_i, other_i) {
+                    calc_fn(curr_v_ref, other_v_ref, 1, write_to);
+                }
+            },
+            &calc_fn,
+            &mut displacement,
+        ));
+
+        let mut expected_displacement = [0.0; D];
+        for value_j in 0..len {
+            if value_j != value_i && !is_connected(value_i, value_j) {
+                calc_fn(
+                    &values[value_i],
+                    &values[value_j],
+                    1,
+                    &mut expected_displacement,
+                );
+            }
+        }
+        assert_values_close(&displacement, &expected_displacement, 1e-9);
+    }
+    Ok(())
+}
+
+#[test]
+fn check_calc_excluding_on_100_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 100;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<2>(1.2);
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<2>(1.0, 0.2);
+
+    for value_i in 0..len {
+        let excluded: Vec<usize> = (0..len)
+            .filter(|j| (value_i + j).is_multiple_of(5))
+            .collect();
+
+        let mut displacement = [0.0; D];
+        assert!(bht.calc_force_on_value_excluding(
+            value_i,
+            &excluded,
+            zbht::utils::criterion_fn(|_, _, _| -> bool { false }),
+            &calc_fn,
+            &mut displacement,
+        ));
+        let mut expected_displacement = [0.0; D];
+        for value_j in 0..len {
+            if value_j != value_i && !excluded.contains(&value_j) {
+                calc_fn(
+                    &values[value_i],
+                    &values[value_j],
+                    1,
+                    &mut expected_displacement,
+                );
+            }
+        }
+        assert_values_close(&displacement, &expected_displacement, 1e-9);
+
+        let mut displacement = [0.0; D];
+        assert!(bht.calc_force_on_value_excluding(
+            value_i,
+            &excluded,
+            &is_super_fn,
+            &calc_fn,
+            &mut displacement,
+        ));
+        let mut expected_displacement = [0.0; D];
+        bht.calc_force_on_value(value_i, &is_super_fn, &calc_fn, &mut expected_displacement);
+        for value_j in excluded.iter().cloned() {
+            if value_j != value_i {
+                let mut excluded_displacement = [0.0; D];
+                calc_fn(
+                    &values[value_i],
+                    &values[value_j],
+                    1,
+                    &mut excluded_displacement,
+                );
+                for d in 0..D {
+                    expected_displacement[d] -= excluded_displacement[d];
+                }
+            }
+        }
+        assert_values_close(&displacement, &expected_displacement, 1e-9);
+    }
+    Ok(())
+}
+
+#[test]
+fn check_softened_gravity_force_is_gradient_of_potential() -> Result<(), Box<dyn std::error::Error>>
+{
+    let (g, softening, weight) = (1.5, 2.0, 3.0);
+    let plummer_fn = zbht::utils::factory_of_plummer_gravity_force_with_potential_calc_fn::<1, f64>(
+        g, softening,
+    );
+    let spline_fn =
+        zbht::utils::factory_of_spline_gravity_force_with_potential_calc_fn::<1, f64>(g, softening);
+
+    let step = 1e-6;
+    for calc_fn in [
+        &plummer_fn as &dyn Fn(&[f64; 1], &[f64; 1], f64, &mut ([f64; 1], f64)),
+        &spline_fn,
+    ] {
+        // Around both pieces of the spline, their joint, and the softening length.
+        for dis in [0.3, 0.9, 1.0, 1.4, 2.0, 2.5, 5.0] {
+            let mut ans = ([0.0; 1], 0.0);
+            calc_fn(&[0.0], &[dis], weight, &mut ans);
+            let (mut ans_before, mut ans_after) = (([0.0; 1], 0.0), ([0.0; 1], 0.0));
+            calc_fn(&[-step], &[dis], weight, &mut ans_before);
+            calc_fn(&[step], &[dis], weight, &mut ans_after);
+            let expected_force = -(ans_after.1 - ans_before.1) / (2.0 * step);
+            assert!(
+                (ans.0[0] - expected_force).abs() < 1e-6,
+                "Force {} at {dis}, expected {expected_force}",
+                ans.0[0]
+            );
+        }
+    }
+
+    let mut ans = ([0.0; 1], 0.0);
+    spline_fn(&[0.0], &[softening], weight, &mut ans);
+    assert!((ans.0[0] - g * weight / softening.powi(2)).abs() < 1e-9);
+    assert!((ans.1 + g * weight / softening).abs() < 1e-9);
+    Ok(())
+}
+
+#[test]
+fn check_tsne_gradient_on_300_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 300;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let mut rng = rand::thread_rng();
+    let mut p_pairs: Vec<(usize, usize, Fnum)> = Vec::new();
+    for i in 0..len {
+        for j in (i + 1)..len.min(i + 4) {
+            p_pairs.push((i, j, rng.gen_range(0.0..1.0) / (len as Fnum)));
+        }
+    }
+
+    let mut expected_z = 0.0;
+    for i in 0..len {
+        for j in 0..len {
+            if i != j {
+                let dis_pow2: Fnum = (0..D).map(|d| (values[i][d] - values[j][d]).powi(2)).sum();
+                expected_z += 1.0 / (1.0 + dis_pow2);
+            }
+        }
+    }
+    let mut expected_gradients = vec![[0.0; D]; len];
+    for i in 0..len {
+        for j in 0..len {
+            if i == j {
+                continue;
+            }
+            let dis_pow2: Fnum = (0..D).map(|d| (values[i][d] - values[j][d]).powi(2)).sum();
+            let q = 1.0 / (1.0 + dis_pow2);
+            let p = p_pairs
+                .iter()
+                .find(|(pi, pj, _)| (*pi, *pj) == (i.min(j), i.max(j)))
+                .map_or(0.0, |(_, _, p)| *p);
+            for d in 0..D {
+                expected_gradients[i][d] +=
+                    4.0 * (p * q - q * q / expected_z) * (values[i][d] - values[j][d]);
+            }
+        }
+    }
+
+    let mut gradients = vec![[0.0; D]; len];
+    let z = zbht::utils::tsne::calc_gradient(
+        &bht,
+        zbht::utils::criterion_fn(|_, _, _| -> bool { false }),
+        &p_pairs,
+        &mut gradients,
+    )
+    .expect("The lengths should match");
+    assert!((z - expected_z).abs() < 1e-9 * expected_z);
+    for (gradient, expected_gradient) in gradients.iter().zip(expected_gradients.iter()) {
+        assert_values_close(gradient, expected_gradient, 1e-12);
+    }
+
+    let mut gradients = vec![[0.0; D]; len];
+    let z = zbht::utils::tsne::calc_gradient(
+        &bht,
+        zbht::utils::factory_of_is_super_node_fn::<2>(0.5),
+        &p_pairs,
+        &mut gradients,
+    )
+    .expect("The lengths should match");
+    assert!((z - expected_z).abs() < 1e-2 * expected_z);
+    let (mut err_pow2, mut exact_pow2) = (0.0, 0.0);
+    for (gradient, expected_gradient) in gradients.iter().zip(expected_gradients.iter()) {
+        for d in 0..D {
+            err_pow2 += (gradient[d] - expected_gradient[d]).powi(2);
+            exact_pow2 += expected_gradient[d].powi(2);
+        }
+    }
+    let rel_err = (err_pow2 / exact_pow2).sqrt();
+    assert!(
+        rel_err < 0.05,
+        "Relative error of the t-SNE gradient: {rel_err}"
+    );
+    Ok(())
+}
+
+#[test]
+fn check_total_potential_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let weights: Vec<Fnum> = generate_random_values::<1>(len, &[0.1..5.0])
+        .into_iter()
+        .map(|w| w[0])
+        .collect();
+    let mut bht: BHTree<D> = BHTree::with_bounding_and_capacity(&[0.0, 0.0], 10.0, len);
+    for (value, weight) in values.iter().zip(weights.iter()) {
+        bht.push_weighted(value, *weight);
+    }
+
+    let potential_fn =
+        zbht::utils::factory_of_plummer_gravity_potential_calc_fn::<D, f64>(1.0, 0.1);
+
+    let mut expected_total = 0.0;
+    for i in 0..len {
+        for j in (i + 1)..len {
+            let mut potential = 0.0;
+            potential_fn(&values[i], &values[j], weights[j], &mut potential);
+            expected_total += weights[i] * potential;
+        }
+    }
+
+    let total = bht.calc_total_potential(
+        |_: &[Fnum; D], _: Fnum, _: &[Fnum; D], _: Fnum| -> bool { false },
+        &potential_fn,
+    );
+    assert!((total - expected_total).abs() < 1e-9 * expected_total.abs());
+
+    let total = bht.calc_total_potential(
+        zbht::utils::factory_of_is_well_separated_fn::<2, f64>(0.5),
+        &potential_fn,
+    );
+    assert!(
+        (total - expected_total).abs() < 1e-2 * expected_total.abs(),
+        "     Got:{total}\nExpected:{expected_total}"
+    );
+    Ok(())
+}
+
+#[test]
+fn check_total_charged_potential_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let charges: Vec<Fnum> = generate_random_values::<1>(len, &[0.1..1.0])
+        .into_iter()
+        .enumerate()
+        .map(|(i, q)| if i % 3 == 0 { -q[0] } else { q[0] })
+        .collect();
+    let mut bht: BHTree<D> = BHTree::with_bounding_and_capacity(&[0.0, 0.0], 10.0, len);
+    for (value, charge) in values.iter().zip(charges.iter()) {
+        bht.push_charged(value, *charge);
+    }
+
+    let potential_fn = zbht::utils::factory_of_coulomb_potential_calc_fn::<D, f64>(1.0);
+
+    let mut expected_total = 0.0;
+    let mut expected_abs_total = 0.0;
+    for i in 0..len {
+        for j in (i + 1)..len {
+            let mut potential = 0.0;
+            potential_fn(
+                &values[i],
+                &values[j],
+                charges[j],
+                &values[j],
+                0.0,
+                &mut potential,
+            );
+            expected_total += charges[i] * potential;
+            expected_abs_total += (charges[i] * potential).abs();
+        }
+    }
+
+    let total = bht.calc_total_charged_potential(
+        |_: &[Fnum; D], _: Fnum, _: &[Fnum; D], _: Fnum| -> bool { false },
+        &potential_fn,
+    );
+    assert!((total - expected_total).abs() < 1e-9 * expected_abs_total);
+
+    let total = bht.calc_total_charged_potential(
+        zbht::utils::factory_of_is_well_separated_fn::<2, f64>(0.5),
+        &potential_fn,
+    );
+    assert!(
+        (total - expected_total).abs() < 1e-2 * expected_abs_total,
+        "     Got:{total}\nExpected:{expected_total}"
+    );
+    Ok(())
+}
+
+#[test]
+fn check_knn_finds_value_on_rounded_child_boundary() -> Result<(), Box<dyn std::error::Error>> {
+    // The spacing of floats in [0.5, 1).
+    let ulp = Fnum::EPSILON / 2.0;
+    // The value 0 is on the root's center, so it is divided into the upper child, whose lower bound is rounded up to two spacings above it.
+    let value_0: [Fnum; 2] = [0.7, 0.0];
+    // The value 1 is in the lower child, farther from the point than the value 0, but nearer than the rounded bound.
+    let value_1: [Fnum; 2] = [0.7 - ulp, 1.5 * ulp];
+    let point: [Fnum; 2] = [0.7 - ulp, 0.0];
+    let bht: BHTree<2> =
+        BHTree::with_bounding_and_values_and_limit(&[0.7, 0.0], 1.3, &[value_0, value_1], 1e-300);
+
+    assert_eq!(bht.knn(&point, 1), vec![(0, ulp)]);
+    assert_eq!(bht.query_ball_vec(&point, ulp), vec![0]);
+    Ok(())
+}
+
+#[test]
+fn check_calc_with_error_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let (k, c) = (1.0, 0.2);
+    let calc_fn = zbht::utils::factory_of_repulsive_displacement_calc_fn::<D>(k, c);
+    let error_fn = zbht::utils::factory_of_repulsive_displacement_error_fn::<D, f64>(k, c);
+
+    let mut last_total_error = 0.0;
+    for theta in [0.0, 0.3, 0.6, 1.0] {
+        let is_super_fn = zbht::utils::factory_of_is_super_node_fn::<D>(theta);
+        let mut total_error = 0.0;
+        let mut displacements = vec![[0.0; D]; len];
+        let mut errors = vec![0.0; len];
+        for i in 0..len {
+            let mut expected_displacement = [0.0; D];
+            for j in 0..len {
+                if j != i {
+                    calc_fn(&values[i], &values[j], 1, &mut expected_displacement);
+                }
+            }
+
+            let mut ans_displacement = [0.0; D];
+            let mut ans_error = 0.0;
+            assert!(bht.calc_force_on_value_with_error(
+                i,
+                &is_super_fn,
+                &calc_fn,
+                &error_fn,
+                &mut ans_displacement,
+                &mut ans_error,
+            ));
+            let actual_error: Fnum = (0..D)
+                .map(|d| (ans_displacement[d] - expected_displacement[d]).powi(2))
+                .sum::<Fnum>()
+                .sqrt();
+            assert!(actual_error <= ans_error + 1e-12);
+            total_error += ans_error;
+            displacements[i] = ans_displacement;
+            errors[i] = ans_error;
+        }
+
+        let mut all_displacements = vec![[0.0; D]; len];
+        let mut all_errors = vec![0.0; len];
+        assert!(bht.calc_force_on_all_values_with_error(
+            &is_super_fn,
+            &calc_fn,
+            &error_fn,
+            &mut all_displacements,
+            &mut all_errors,
+        ));
+        assert_eq!(all_displacements, displacements);
+        assert_eq!(all_errors, errors);
+        if theta == 0.0 {
+            assert_eq!(total_error, 0.0);
+        } else {
+            assert!(total_error > last_total_error);
+        }
+        last_total_error = total_error;
+    }
+    Ok(())
+}
+
+#[test]
+fn check_knn_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let mut values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    // Some values at the same positions.
+    for i in 0..10 {
+        values[len - 1 - i] = values[i];
+    }
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let calc_expected = |point: &[Fnum; D], k: usize, skip_i: Option<usize>| {
+        let mut expected: Vec<(usize, Fnum)> = (0..len)
+            .filter(|i| Some(*i) != skip_i)
+            .map(|i| {
+                let dis_pow2: Fnum = (0..D).map(|d| (values[i][d] - point[d]).powi(2)).sum();
+                (i, dis_pow2.sqrt())
+            })
+            .collect();
+        expected.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+        expected.truncate(k);
+        expected
+    };
+
+    let mut rng = rand::thread_rng();
+    for k in [0, 1, 5, 30] {
+        for _ in 0..100 {
+            let point = [rng.gen_range(-12.0..12.0), rng.gen_range(-12.0..12.0)];
+            assert_eq!(bht.knn(&point, k), calc_expected(&point, k, None));
+        }
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(
+                bht.knn_of_value(i, k),
+                Some(calc_expected(value, k, Some(i)))
+            );
+        }
+    }
+    assert_eq!(bht.knn(&[0.0, 0.0], len + 1).len(), len);
+    assert_eq!(
+        bht.knn(&[0.0, 0.0], usize::MAX),
+        calc_expected(&[0.0, 0.0], len, None)
+    );
+    assert_eq!(
+        bht.knn_of_value(0, usize::MAX),
+        Some(calc_expected(&values[0], len, Some(0)))
+    );
+    assert_eq!(bht.knn_of_value(len, 1), None);
+    Ok(())
+}
+
+#[test]
+fn check_query_ball_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let mut values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    // Some values at the same positions.
+    for i in 0..10 {
+        values[len - 1 - i] = values[i];
+    }
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let calc_expected = |point: &[Fnum; D], r: Fnum, skip_i: Option<usize>| -> Vec<usize> {
+        (0..len)
+            .filter(|i| Some(*i) != skip_i)
+            .filter(|i| {
+                let dis_pow2: Fnum = (0..D).map(|d| (values[*i][d] - point[d]).powi(2)).sum();
+                dis_pow2 <= r * r
+            })
+            .collect()
+    };
+
+    let mut rng = rand::thread_rng();
+    for r in [0.0, 0.5, 2.0, 8.0, 30.0] {
+        for _ in 0..100 {
+            let point = [rng.gen_range(-12.0..12.0), rng.gen_range(-12.0..12.0)];
+            assert_eq!(
+                bht.query_ball_vec(&point, r),
+                calc_expected(&point, r, None)
+            );
+        }
+        for (i, value) in values.iter().enumerate() {
+            let mut ans = Vec::new();
+            assert!(bht.query_ball_of_value(i, r, &mut |value_i| ans.push(value_i)));
+            ans.sort();
+            assert_eq!(ans, calc_expected(value, r, Some(i)));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn check_query_ball_in_one_leaf_below_limit() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let values = [[0.0, 0.0], [0.1, 0.0], [0.0, 0.1], [0.1, 0.1], [0.05, 0.05]];
+    let bht: BHTree<D> = BHTree::with_values_and_limit(&values, 1.0);
+    assert_eq!(bht.get_total_nodes_num(), 1);
+
+    assert_eq!(bht.query_ball_vec(&[0.0, 0.0], 0.1), vec![0, 1, 2, 4]);
+    assert_eq!(bht.query_ball_vec(&[0.0, 0.0], 0.2), vec![0, 1, 2, 3, 4]);
+    assert!(bht.query_ball_vec(&[0.5, 0.5], 0.1).is_empty());
+
+    let mut ans = Vec::new();
+    assert!(bht.query_ball_of_value(4, 0.08, &mut |value_i| ans.push(value_i)));
+    ans.sort();
+    assert_eq!(ans, vec![0, 1, 2, 3]);
+    Ok(())
+}
+
+#[test]
+fn check_query_aabb_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..300 {
+        let min = [rng.gen_range(-12.0..12.0), rng.gen_range(-12.0..12.0)];
+        let max = [
+            min[0] + rng.gen_range(-1.0..15.0),
+            min[1] + rng.gen_range(-1.0..15.0),
+        ];
+        let expected: Vec<usize> = (0..len)
+            .filter(|i| (0..D).all(|d| values[*i][d] >= min[d] && values[*i][d] <= max[d]))
+            .collect();
+        assert_eq!(bht.query_aabb_vec(&min, &max), expected);
+    }
+
+    // The box of a value itself.
+    for (i, value) in values.iter().enumerate() {
+        assert!(bht.query_aabb_vec(value, value).contains(&i));
+    }
+    assert_eq!(
+        bht.query_aabb_vec(&[-10.0, -10.0], &[10.0, 10.0]),
+        (0..len).collect::<Vec<usize>>()
+    );
+    Ok(())
+}
+
+#[test]
+fn check_pairs_within_on_1000_random_values() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let len = 1000;
+    let mut values = generate_random_values(len, &[-10.0..10.0, -10.0..10.0]);
+    // Some values at the same positions.
+    for i in 0..10 {
+        values[len - 1 - i] = values[i];
+    }
+    let bht: BHTree<D> = BHTree::from_values(&values);
+
+    for r in [0.0, 0.3, 1.0, 4.0] {
+        let mut expected = Vec::new();
+        for i in 0..len {
+            for j in (i + 1)..len {
+                let dis: Fnum = (0..D)
+                    .map(|d| (values[i][d] - values[j][d]).powi(2))
+                    .sum::<Fnum>()
+                    .sqrt();
+                if dis < r {
+                    expected.push((i, j, dis));
+                }
+            }
+        }
+
+        let mut ans = Vec::new();
+        bht.for_each_pair_within(r, |i, j, dis| ans.push((i, j, dis)));
+        ans.sort_by_key(|pair| (pair.0, pair.1));
+        assert_eq!(ans.len(), expected.len());
+        for (ans_pair, expected_pair) in ans.iter().zip(expected.iter()) {
+            assert_eq!((ans_pair.0, ans_pair.1), (expected_pair.0, expected_pair.1));
+            assert!((ans_pair.2 - expected_pair.2).abs() < 1e-12);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn check_pairs_within_in_one_leaf_below_limit() -> Result<(), Box<dyn std::error::Error>> {
+    const D: usize = 2;
+    let values = [[0.0, 0.0], [0.1, 0.0], [0.0, 0.1], [0.0, 0.0], [5.0, 5.0]];
+    let bht: BHTree<D> = BHTree::with_values_and_limit(&values, 1.0);
+
+    let mut ans = Vec::new();
+    bht.for_each_pair_within(0.12, |i, j, _| ans.push((i, j)));
+    ans.sort();
+    assert_eq!(ans, vec![(0, 1), (0, 2), (0, 3), (1, 3), (2, 3)]);
+    Ok(())
+}
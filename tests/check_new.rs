@@ -353,3 +353,118 @@ fn check_panic_when_updated_value_is_nan() {
 
     bht.update(2, &[0.0, f64::NAN]);
 }
+
+#[test]
+fn check_from_values_with_enclosing_bounding() -> Result<(), Box<dyn std::error::Error>> {
+    let vals: Vec<[f64; 2]> = vec![[1.0, 3.0], [3.0, 1.0], [-1.0, -1.0], [2.0, -0.5]];
+
+    let bht: BHTree<2> = BHTree::from_values(&vals);
+
+    let calc_bht_ser = bht.calc_serialized();
+    assert_eq!(calc_bht_ser.get_bcs()[..2], [1.0, 1.0]);
+    assert!(calc_bht_ser.get_brs()[0] > 2.0 && calc_bht_ser.get_brs()[0] < 2.0 + 1e-12);
+
+    let expected_bht: BHTree<2> =
+        BHTree::with_bounding_and_values(&[1.0, 1.0], calc_bht_ser.get_brs()[0], &vals);
+    assert_bht_serde_eq(&calc_bht_ser, &expected_bht.calc_serialized());
+    Ok(())
+}
+
+#[test]
+fn check_recompute_bounds_after_values_moved_inward() -> Result<(), Box<dyn std::error::Error>> {
+    let vals: Vec<[f64; 2]> = vec![[1.0, 3.0], [3.0, 1.0], [-100.0, -100.0], [200.0, -0.5]];
+
+    let mut bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0, 0.0], 2.0, &vals);
+    bht.update_weight(1, 3.0);
+    bht.update(2, &[-1.0, -1.0]);
+    bht.update(3, &[2.0, -0.5]);
+
+    bht.recompute_bounds();
+
+    let calc_bht_ser = bht.calc_serialized();
+    assert_eq!(calc_bht_ser.get_bcs()[..2], [1.0, 1.0]);
+    assert!(calc_bht_ser.get_brs()[0] > 2.0 && calc_bht_ser.get_brs()[0] < 2.0 + 1e-12);
+
+    let mut expected_bht: BHTree<2> =
+        BHTree::with_bounding_and_capacity(&[1.0, 1.0], calc_bht_ser.get_brs()[0], 4);
+    expected_bht.push(&[1.0, 3.0]);
+    expected_bht.push_weighted(&[3.0, 1.0], 3.0);
+    expected_bht.push(&[-1.0, -1.0]);
+    expected_bht.push(&[2.0, -0.5]);
+
+    assert_bht_serde_eq(&calc_bht_ser, &expected_bht.calc_serialized());
+    assert_eq!(bht.get_weight(1), Some(3.0));
+    Ok(())
+}
+
+#[test]
+fn check_node_cursor_walks() -> Result<(), Box<dyn std::error::Error>> {
+    let mut vals: Vec<[f64; 2]> = Vec::new();
+    for i in 0..10 {
+        for j in 0..10 {
+            vals.push([i as f64 * 0.7 - 3.0, (j * j) as f64 * 0.1 - 4.0]);
+        }
+    }
+    let mut bht: BHTree<2> = BHTree::with_bounding_and_values(&[0.0, 0.0], 8.0, &vals);
+    bht.remove(5);
+    bht.remove(42);
+    let len = vals.len() - 2;
+
+    let root = bht.root().unwrap();
+    assert_eq!(root.parent(), None);
+    assert_eq!(root.count(), len);
+    assert!(format!("{root:?}").starts_with("NodeRef { node: In("));
+
+    let depth_first: Vec<_> = root.depth_first().collect();
+    let breadth_first: Vec<_> = root.breadth_first().collect();
+    assert_eq!(depth_first.len(), bht.get_total_nodes_num());
+    assert_eq!(breadth_first.len(), bht.get_total_nodes_num());
+    assert_eq!(depth_first[0], root);
+    assert_eq!(breadth_first[0], root);
+
+    for node in depth_first.iter() {
+        let children: Vec<_> = node.children().collect();
+        if node.is_leaf() {
+            assert!(children.is_empty());
+            let values = node.values().unwrap();
+            assert_eq!(values.len(), node.count());
+            for d in 0..2 {
+                let mean = values.iter().map(|i| bht.get(*i).unwrap()[d]).sum::<f64>()
+                    / values.len() as f64;
+                assert!((node.value_center()[d] - mean).abs() < 1e-12);
+            }
+        } else {
+            assert!(node.values().is_none());
+            assert_eq!(
+                children.iter().map(|child| child.count()).sum::<usize>(),
+                node.count()
+            );
+        }
+        for child in children.iter() {
+            assert_eq!(child.parent(), Some(*node));
+            assert_eq!(child.half_width(), node.half_width() / 2.0);
+            for d in 0..2 {
+                let diff = (child.bounding_center()[d] - node.bounding_center()[d]).abs();
+                assert_eq!(diff, child.half_width());
+            }
+        }
+        assert_eq!(node.summary().count, node.count());
+        assert_eq!(node.weight(), node.count() as f64);
+    }
+
+    // Breadth-first goes level by level.
+    for (prev, next) in breadth_first.iter().zip(breadth_first[1..].iter()) {
+        assert!(prev.half_width() >= next.half_width());
+    }
+
+    // The leaves hold all the values once.
+    let mut value_indices: Vec<usize> = depth_first
+        .iter()
+        .filter_map(|node| node.values())
+        .flatten()
+        .cloned()
+        .collect();
+    value_indices.sort();
+    assert_eq!(value_indices, (0..len).collect::<Vec<usize>>());
+    Ok(())
+}